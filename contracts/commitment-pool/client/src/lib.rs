@@ -0,0 +1,345 @@
+//! Commitment Pool client — note memos
+//!
+//! ⚠️  PRE-ALPHA / RESEARCH — NOT FOR PRODUCTION USE ⚠️
+//!
+//! A payer who deposits a note on behalf of someone else attaches the note
+//! opening (secret, nullifier, amount), encrypted to the recipient's
+//! viewing key, as the `memo` of `CommitmentPool::deposit`. The contract
+//! emits it untouched in the `Deposited` event. The recipient scans deposit
+//! events and trial-decrypts every memo; the ones that open are their notes.
+//!
+//! ## Memo format (v1)
+//!
+//! ```text
+//! version (1) || ephemeral_pk (32) || ChaCha20-Poly1305(plaintext) (80 + 16)
+//! plaintext = secret (32) || nullifier (32) || amount (16, i128 big-endian)
+//! ```
+//!
+//! key = HKDF-SHA256(ikm = X25519(ephemeral_sk, viewing_pk),
+//!                   salt = ephemeral_pk || viewing_pk, info = MEMO_INFO)
+//!
+//! Every memo uses a fresh ephemeral key, so the all-zero nonce is never
+//! reused under the same key. The commitment is the AEAD associated data:
+//! a memo copied onto another deposit does not decrypt.
+//!
+//! A decrypted memo is only a claim by the payer. Wallets must recompute
+//! Poseidon(secret, amount, nullifier) and compare it with the commitment
+//! before treating the note as received; `scan` already rejects memos whose
+//! amount differs from the deposited amount.
+//!
+//! Notes created by `CommitmentPool::transfer` carry memos of the same
+//! format in their `TransferNote` events. Those events publish the note's
+//! leaf, Poseidon(commitment, amount), instead of its commitment, and the
+//! leaf is the associated data. Their amount is private, so open them with
+//! `decrypt_memo` directly, passing the leaf; recomputing the leaf from the
+//! opening is then the only proof the note is real.
+//!
+//! A deposit made with `refund_after` set can be taken back by its
+//! depositor once that timestamp passes, unless the note is spent first.
+//! `scan` reports the timestamp; wallets should spend or re-deposit such a
+//! note before then, or not count it as received.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::{CryptoRngCore, OsRng};
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+/// Current memo format version (first byte of every memo).
+pub const MEMO_VERSION: u8 = 1;
+
+/// HKDF info string of memo format v1.
+pub const MEMO_INFO: &[u8] = b"commitment-pool/note-memo/v1";
+
+const PLAINTEXT_LEN: usize = 32 + 32 + 16;
+const TAG_LEN: usize = 16;
+
+/// Length of a v1 memo. Well under the contract's `MAX_MEMO_LEN`.
+pub const MEMO_LEN: usize = 1 + 32 + PLAINTEXT_LEN + TAG_LEN;
+
+/// The private opening of a note, as produced by generateCommitmentKey().
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoteOpening {
+    pub secret: [u8; 32],
+    pub nullifier: [u8; 32],
+    /// Amount in stroops
+    pub amount: i128,
+}
+
+/// Proof system of a note's commitment set, mirroring the contract's
+/// `ProofSystem`. Being a `#[contracttype]` unit enum, it is encoded in
+/// events as a one-element vector holding the variant name as a symbol,
+/// e.g. `Vec[Symbol("Groth16")]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofSystem {
+    Groth16,
+    UltraHonk,
+    Risc0,
+}
+
+impl ProofSystem {
+    /// The variant name the contract encodes this system as.
+    pub fn as_symbol(self) -> &'static str {
+        match self {
+            ProofSystem::Groth16 => "Groth16",
+            ProofSystem::UltraHonk => "UltraHonk",
+            ProofSystem::Risc0 => "Risc0",
+        }
+    }
+
+    /// Decode the symbol inside an encoded `ProofSystem`, or None if it
+    /// names no known system.
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        [ProofSystem::Groth16, ProofSystem::UltraHonk, ProofSystem::Risc0]
+            .into_iter()
+            .find(|system| system.as_symbol() == symbol)
+    }
+}
+
+/// X25519 key a recipient publishes so payers can address memos to them.
+pub struct ViewingKey(StaticSecret);
+
+impl ViewingKey {
+    pub fn generate() -> Self {
+        Self(StaticSecret::random_from_rng(OsRng))
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(StaticSecret::from(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// The public half, shared with payers.
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&self.0).to_bytes()
+    }
+}
+
+/// The fields of a `Deposited` event a scanner needs, decoded by the
+/// caller's RPC layer. In the event data tuple they are at positions
+/// 0 (commitment), 1 (amount), 2 (token), 4 (leaf_index), 6 (refund_after),
+/// 7 (memo) and 8 (system).
+#[derive(Clone, Debug)]
+pub struct DepositedEvent {
+    pub commitment: [u8; 32],
+    pub amount: i128,
+    /// Token contract address (strkey)
+    pub token: String,
+    pub leaf_index: u32,
+    /// Ledger timestamp after which the depositor may refund the note
+    pub refund_after: Option<u64>,
+    pub memo: Option<Vec<u8>>,
+    /// Commitment set the leaf joined; `leaf_index` is only meaningful
+    /// within it
+    pub system: ProofSystem,
+}
+
+/// A note found by `scan`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceivedNote {
+    pub opening: NoteOpening,
+    pub commitment: [u8; 32],
+    pub token: String,
+    pub leaf_index: u32,
+    pub system: ProofSystem,
+    /// Set if the depositor can take the note back after this timestamp
+    pub refund_after: Option<u64>,
+}
+
+/// Encrypt `note` to `viewing_pk` for the deposit of `commitment`.
+pub fn encrypt_memo(
+    viewing_pk: &[u8; 32],
+    commitment: &[u8; 32],
+    note: &NoteOpening,
+) -> Vec<u8> {
+    encrypt_memo_with_rng(viewing_pk, commitment, note, &mut OsRng)
+}
+
+/// `encrypt_memo` with a caller-supplied RNG for the ephemeral key.
+pub fn encrypt_memo_with_rng(
+    viewing_pk: &[u8; 32],
+    commitment: &[u8; 32],
+    note: &NoteOpening,
+    rng: &mut impl CryptoRngCore,
+) -> Vec<u8> {
+    let ephemeral = EphemeralSecret::random_from_rng(rng);
+    let ephemeral_pk = PublicKey::from(&ephemeral).to_bytes();
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(*viewing_pk));
+    let cipher = memo_cipher(shared.as_bytes(), &ephemeral_pk, viewing_pk);
+
+    let mut plaintext = [0u8; PLAINTEXT_LEN];
+    plaintext[..32].copy_from_slice(&note.secret);
+    plaintext[32..64].copy_from_slice(&note.nullifier);
+    plaintext[64..].copy_from_slice(&note.amount.to_be_bytes());
+
+    let ciphertext = cipher
+        .encrypt(
+            &Nonce::default(),
+            Payload {
+                msg: &plaintext,
+                aad: commitment,
+            },
+        )
+        .expect("ChaCha20-Poly1305 encryption of a fixed-size buffer cannot fail");
+
+    let mut memo = Vec::with_capacity(MEMO_LEN);
+    memo.push(MEMO_VERSION);
+    memo.extend_from_slice(&ephemeral_pk);
+    memo.extend_from_slice(&ciphertext);
+    memo
+}
+
+/// Try to open `memo` with `key`. Returns None if the memo is malformed,
+/// addressed to someone else, or attached to a different commitment.
+pub fn decrypt_memo(key: &ViewingKey, commitment: &[u8; 32], memo: &[u8]) -> Option<NoteOpening> {
+    if memo.len() != MEMO_LEN || memo[0] != MEMO_VERSION {
+        return None;
+    }
+    let ephemeral_pk: [u8; 32] = memo[1..33].try_into().ok()?;
+    let shared = key.0.diffie_hellman(&PublicKey::from(ephemeral_pk));
+    let cipher = memo_cipher(shared.as_bytes(), &ephemeral_pk, &key.public_key());
+
+    let plaintext = cipher
+        .decrypt(
+            &Nonce::default(),
+            Payload {
+                msg: &memo[33..],
+                aad: commitment,
+            },
+        )
+        .ok()?;
+
+    Some(NoteOpening {
+        secret: plaintext[..32].try_into().ok()?,
+        nullifier: plaintext[32..64].try_into().ok()?,
+        amount: i128::from_be_bytes(plaintext[64..].try_into().ok()?),
+    })
+}
+
+/// Trial-decrypt the memos of `events` and return the notes addressed to
+/// `key`, in event order.
+pub fn scan<'a>(
+    key: &ViewingKey,
+    events: impl IntoIterator<Item = &'a DepositedEvent>,
+) -> Vec<ReceivedNote> {
+    events
+        .into_iter()
+        .filter_map(|event| {
+            let opening = decrypt_memo(key, &event.commitment, event.memo.as_deref()?)?;
+            if opening.amount != event.amount {
+                return None;
+            }
+            Some(ReceivedNote {
+                opening,
+                commitment: event.commitment,
+                token: event.token.clone(),
+                leaf_index: event.leaf_index,
+                system: event.system,
+                refund_after: event.refund_after,
+            })
+        })
+        .collect()
+}
+
+fn memo_cipher(shared: &[u8; 32], ephemeral_pk: &[u8; 32], viewing_pk: &[u8; 32]) -> ChaCha20Poly1305 {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_pk);
+    salt[32..].copy_from_slice(viewing_pk);
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(MEMO_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn note(seed: u8, amount: i128) -> NoteOpening {
+        NoteOpening {
+            secret: [seed; 32],
+            nullifier: [seed + 1; 32],
+            amount,
+        }
+    }
+
+    fn event(commitment: [u8; 32], amount: i128, leaf_index: u32, memo: Option<Vec<u8>>) -> DepositedEvent {
+        DepositedEvent {
+            commitment,
+            amount,
+            token: "CTOKEN".into(),
+            leaf_index,
+            refund_after: None,
+            memo,
+            system: ProofSystem::Groth16,
+        }
+    }
+
+    #[test]
+    fn test_memo_round_trip() {
+        let key = ViewingKey::generate();
+        let commitment = [9u8; 32];
+        let memo = encrypt_memo(&key.public_key(), &commitment, &note(1, 100_000_000));
+        assert_eq!(memo.len(), MEMO_LEN);
+        assert_eq!(decrypt_memo(&key, &commitment, &memo), Some(note(1, 100_000_000)));
+    }
+
+    #[test]
+    fn test_memo_bound_to_key_and_commitment() {
+        let key = ViewingKey::generate();
+        let other = ViewingKey::generate();
+        let memo = encrypt_memo(&key.public_key(), &[9u8; 32], &note(1, 100_000_000));
+        assert_eq!(decrypt_memo(&other, &[9u8; 32], &memo), None);
+        assert_eq!(decrypt_memo(&key, &[8u8; 32], &memo), None);
+    }
+
+    #[test]
+    fn test_scan_finds_own_notes() {
+        let key = ViewingKey::generate();
+        let other = ViewingKey::generate();
+        let mine = encrypt_memo(&key.public_key(), &[1u8; 32], &note(1, 100_000_000));
+        let theirs = encrypt_memo(&other.public_key(), &[2u8; 32], &note(3, 100_000_000));
+        // Memo claims a different amount than was deposited
+        let lying = encrypt_memo(&key.public_key(), &[3u8; 32], &note(5, 500_000_000));
+
+        let events = [
+            event([1u8; 32], 100_000_000, 0, Some(mine)),
+            event([2u8; 32], 100_000_000, 1, Some(theirs)),
+            event([4u8; 32], 100_000_000, 2, None),
+            event([3u8; 32], 100_000_000, 3, Some(lying)),
+        ];
+        let found = scan(&key, &events);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].leaf_index, 0);
+        assert_eq!(found[0].system, ProofSystem::Groth16);
+        assert_eq!(found[0].opening, note(1, 100_000_000));
+    }
+
+    #[test]
+    fn test_scan_reports_refundable_notes() {
+        let key = ViewingKey::generate();
+        let memo = encrypt_memo(&key.public_key(), &[1u8; 32], &note(1, 100_000_000));
+        let refundable = DepositedEvent {
+            refund_after: Some(1_700_000_000),
+            ..event([1u8; 32], 100_000_000, 0, Some(memo))
+        };
+        let found = scan(&key, [&refundable]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].refund_after, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_proof_system_symbols() {
+        for system in [ProofSystem::Groth16, ProofSystem::UltraHonk, ProofSystem::Risc0] {
+            assert_eq!(ProofSystem::from_symbol(system.as_symbol()), Some(system));
+        }
+        assert_eq!(ProofSystem::from_symbol("UltraHonk"), Some(ProofSystem::UltraHonk));
+        assert_eq!(ProofSystem::from_symbol("1"), None);
+        assert_eq!(ProofSystem::from_symbol("groth16"), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Withdraw proof verification backends.
+//!
+//! Every backend proves the same withdraw statement (see the crate docs);
+//! they differ in how the public inputs reach the verifier contract:
+//!
+//! - `Groth16`: circom notes, verified by a TierVerifier instance via
+//!   `verify_groth16(public_inputs, proof)`.
+//! - `UltraHonk`: Noir notes, verified by an ultrahonk-verifier instance via
+//!   `verify_proof_vk(vk_id, public_inputs, proof)`, with the public inputs
+//!   packed as concatenated 32-byte big-endian field elements. The verifier
+//!   admin can re-register `vk_id`, so the backend pins the VK's hash and
+//!   every verification first checks it against `vk_hash_by_id(vk_id)`; a
+//!   new key only takes effect through a governed `SetVerifier`.
+//! - `Risc0`: a RISC Zero guest that commits the packed public inputs as its
+//!   journal, verified by a risc0-groth16-verifier instance. The pool
+//!   recomputes the receipt claim digest from the configured image ID and
+//!   the journal, so the receipt can only attest to this exact statement.
+//!   That contract has no side-effect-free entry point: `verify_and_attest`
+//!   also stores a per-owner attestation and emits an event. The pool calls
+//!   it as owner, so every Risc0 withdrawal overwrites the pool's own
+//!   attestation there with its claim digest. Nothing reads that record; it
+//!   is a harmless, publicly visible by-product.
+
+use soroban_sdk::{
+    contracttype, Address, Bytes, BytesN, Env, IntoVal, Symbol, Val, Vec,
+};
+
+use crate::{verifier, Groth16Proof, PoolError};
+
+pub use risc0_claim::{claim_digest, split_digest};
+
+/// Proof system a commitment scheme belongs to. Notes of different systems
+/// live in separate commitment sets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ProofSystem {
+    Groth16,
+    UltraHonk,
+    Risc0,
+}
+
+/// An ultrahonk-verifier contract and the VK registered for the withdraw
+/// circuit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UltraHonkBackend {
+    pub verifier: Address,
+    pub vk_id: Symbol,
+    /// sha256 of the VK bytes, as returned by `vk_hash_by_id(vk_id)`
+    pub vk_hash: BytesN<32>,
+}
+
+/// A risc0-groth16-verifier contract plus the constants of the receipt
+/// statement: the withdraw guest's image ID and the RISC Zero release's
+/// control root (already split into two field elements) and BN254 control ID.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Risc0Backend {
+    pub verifier: Address,
+    pub image_id: BytesN<32>,
+    pub control_root_0: BytesN<32>,
+    pub control_root_1: BytesN<32>,
+    pub bn254_control_id: BytesN<32>,
+}
+
+/// Verifier configuration of one proof system.
+// Variants only hold host object handles on-chain; the size gap is a
+// testutils artifact.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum VerifierBackend {
+    /// TierVerifier contract address
+    Groth16(Address),
+    UltraHonk(UltraHonkBackend),
+    Risc0(Risc0Backend),
+}
+
+impl VerifierBackend {
+    pub fn system(&self) -> ProofSystem {
+        match self {
+            VerifierBackend::Groth16(_) => ProofSystem::Groth16,
+            VerifierBackend::UltraHonk(_) => ProofSystem::UltraHonk,
+            VerifierBackend::Risc0(_) => ProofSystem::Risc0,
+        }
+    }
+}
+
+/// A withdraw proof; the variant selects the proof system, and with it the
+/// commitment set and verifier.
+#[derive(Clone)]
+#[contracttype]
+pub enum WithdrawProof {
+    Groth16(Groth16Proof),
+    /// Serialized UltraHonk proof
+    UltraHonk(Bytes),
+    /// Seal of a RISC Zero Groth16 receipt
+    Risc0(Groth16Proof),
+}
+
+impl WithdrawProof {
+    pub fn system(&self) -> ProofSystem {
+        match self {
+            WithdrawProof::Groth16(_) => ProofSystem::Groth16,
+            WithdrawProof::UltraHonk(_) => ProofSystem::UltraHonk,
+            WithdrawProof::Risc0(_) => ProofSystem::Risc0,
+        }
+    }
+}
+
+/// Verify `proof` against `public_inputs` with `backend`.
+pub fn verify(
+    env: &Env,
+    backend: &VerifierBackend,
+    public_inputs: &Vec<BytesN<32>>,
+    proof: &WithdrawProof,
+) -> Result<(), PoolError> {
+    match (backend, proof) {
+        (VerifierBackend::Groth16(verifier), WithdrawProof::Groth16(proof)) => {
+            verifier::Client::new(env, verifier)
+                .try_verify_groth16(public_inputs, proof)
+                .map_err(|_| PoolError::InvalidProof)?
+                .map_err(|_| PoolError::InvalidProof)?;
+            Ok(())
+        }
+        (VerifierBackend::UltraHonk(backend), WithdrawProof::UltraHonk(proof)) => {
+            let args: Vec<Val> = (backend.vk_id.clone(),).into_val(env);
+            let vk_hash = env
+                .try_invoke_contract::<Option<BytesN<32>>, soroban_sdk::Error>(
+                    &backend.verifier,
+                    &Symbol::new(env, "vk_hash_by_id"),
+                    args,
+                )
+                .map_err(|_| PoolError::VerifierKeyMismatch)?
+                .map_err(|_| PoolError::VerifierKeyMismatch)?;
+            if vk_hash.as_ref() != Some(&backend.vk_hash) {
+                return Err(PoolError::VerifierKeyMismatch);
+            }
+            let args: Vec<Val> = (
+                backend.vk_id.clone(),
+                pack(env, public_inputs),
+                proof.clone(),
+            )
+                .into_val(env);
+            let ok = env
+                .try_invoke_contract::<bool, soroban_sdk::Error>(
+                    &backend.verifier,
+                    &Symbol::new(env, "verify_proof_vk"),
+                    args,
+                )
+                .map_err(|_| PoolError::InvalidProof)?
+                .map_err(|_| PoolError::InvalidProof)?;
+            if !ok {
+                return Err(PoolError::InvalidProof);
+            }
+            Ok(())
+        }
+        (VerifierBackend::Risc0(backend), WithdrawProof::Risc0(seal)) => {
+            let claim = claim_digest(env, &backend.image_id, &pack(env, public_inputs));
+            let (claim_0, claim_1) = split_digest(env, &claim);
+            let receipt_inputs = Vec::from_array(
+                env,
+                [
+                    backend.control_root_0.clone(),
+                    backend.control_root_1.clone(),
+                    claim_0,
+                    claim_1,
+                    backend.bn254_control_id.clone(),
+                ],
+            );
+            // There is no pure verify entry point: the receipt verifier also
+            // records an attestation for `owner`. The pool attests as itself,
+            // which needs no extra signature, so the record it overwrites is
+            // the pool's own (see the module docs).
+            let args: Vec<Val> = (
+                env.current_contract_address(),
+                claim,
+                receipt_inputs,
+                seal.clone(),
+            )
+                .into_val(env);
+            env.try_invoke_contract::<bool, soroban_sdk::Error>(
+                &backend.verifier,
+                &Symbol::new(env, "verify_and_attest"),
+                args,
+            )
+            .map_err(|_| PoolError::InvalidProof)?
+            .map_err(|_| PoolError::InvalidProof)?;
+            Ok(())
+        }
+        _ => Err(PoolError::InvalidProof),
+    }
+}
+
+/// Public inputs as concatenated 32-byte big-endian field elements.
+pub fn pack(env: &Env, public_inputs: &Vec<BytesN<32>>) -> Bytes {
+    let mut packed = Bytes::new(env);
+    for input in public_inputs.iter() {
+        packed.append(&input.into());
+    }
+    packed
+}
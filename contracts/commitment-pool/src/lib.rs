@@ -10,26 +10,230 @@
 //! ## How it works
 //!
 //! 1. DEPOSIT: A user deposits tokens along with a Poseidon commitment
-//!    C = Poseidon(secret, amount, nullifier). The commitment is recorded
-//!    on-chain but the secret and nullifier stay private with the user.
+//!    C = Poseidon(secret, amount, nullifier). The contract appends the leaf
+//!    L = Poseidon(C, amount) to the token's incremental Poseidon Merkle
+//!    tree (see `merkle`), using the amount it actually received; the secret
+//!    and nullifier stay private with the user. Nothing checks the amount
+//!    inside C, but a note whose C claims more than was deposited has no
+//!    leaf it can be proven against.
 //!
 //! 2. WITHDRAW: Anyone holding (secret, nullifier) can generate a Groth16
-//!    proof proving they know the preimage of C, then call withdraw() with
-//!    any recipient address. The contract verifies the proof on-chain via
-//!    the existing TierVerifier contract, checks the nullifier has not been
+//!    proof that some leaf of the tree is Poseidon(C, amount) with
+//!    C = Poseidon(secret, amount, nullifier),
+//!    against one of the last `ROOT_HISTORY_SIZE` roots or a checkpoint root
+//!    (see "Minimum note age"), then call
+//!    withdraw() with any recipient address, optionally through a relayer
+//!    who submits the transaction and is paid a fee out of the note so the
+//!    recipient needs no XLM. The withdrawal names the root,
+//!    not the commitment, so it cannot be linked to a specific deposit
+//!    beyond the token and amount. The contract verifies the proof on-chain
+//!    via the TierVerifier contract, checks the nullifier hash has not been
 //!    spent, then transfers the funds to the specified recipient.
 //!
-//! This is a bearer-note escrow primitive, not an anonymity-set mixer.
+//!    The nullifier hash N = Poseidon(nullifier) is computed inside the
+//!    circuit from the same nullifier that opens the leaf, so each note has
+//!    exactly one valid N and double-spends are rejected cryptographically.
+//!
+//! 3. JOIN-SPLIT: withdraw_with_change() spends one note, pays out part of
+//!    it and appends the leaf of a change note for the remainder to the same
+//!    tree. The change note is later spent like any deposited note.
+//!
+//! 4. REFUND: a deposit may opt into a refund timestamp. After it, the
+//!    original depositor can reclaim the note by revealing its opening
+//!    (secret, nullifier); the contract recomputes the commitment and marks
+//!    Poseidon(nullifier) spent. This recovers funds if the verifier is
+//!    replaced by one that no longer accepts the note's proofs, at the cost
+//!    of publicly linking the refund to the deposit.
+//!
+//! 5. TRANSFER: transfer() spends up to two notes of a token and appends the
+//!    leaves of up to two new notes of equal total value to the same tree. Nothing
+//!    leaves the pool, so the payee receives a note instead of a public
+//!    payment and can spend it the same way. `Open` mode only.
+//!
+//! A deposit may carry an encrypted memo, emitted with the `Deposited`
+//! event, that hands the note opening to a recipient's viewing key;
+//! transfer outputs carry memos the same way. The
+//! contract treats it as opaque bytes; `client/` implements the encryption
+//! and the trial-decryption scan wallets run over deposit events.
+//!
+//! The anonymity set of a withdrawal is every note in the same commitment
+//! set. A full withdrawal reveals the note amount; a join-split withdrawal
+//! only reveals the amount paid out.
+//!
+//! ## Pool modes
+//!
+//! The mode is fixed at `initialize`:
+//! - `Open`: any positive amount; one commitment set (tree) per token,
+//!   addressed with denomination 0.
+//! - `FixedDenomination`: deposits must match one of the admin-defined
+//!   denominations of the token, and each (token, denomination) pair has its
+//!   own tree, so amounts no longer fingerprint notes. Join-split is not
+//!   available since change notes would not be a denomination.
+//!
+//! ## Governance
+//!
+//! Verifier replacement, guardian changes, protocol fee changes and WASM
+//! upgrades are two-step:
+//! the admin proposes an action, and it can only be executed
+//! `GOVERNANCE_DELAY` seconds later.
+//! Every step emits an event, so depositors who distrust a pending change
+//! have the whole delay to withdraw. Only one proposal is pending at a time;
+//! the admin may cancel it before execution.
+//!
+//! The admin may call `renounce_admin` to freeze the pool for good: no
+//! proposal can be made or executed afterwards, so the pool's WASM and its
+//! verifier backends can no longer be replaced, and token listings,
+//! denominations, the guardian, the minimum note age and the protocol fees
+//! are frozen as they stand. The guardian keeps its time-limited pause.
+//! `is_immutable` and the `AdminRenounced` event record the state.
+//!
+//! Renouncing does not make proof verification final. The verifier
+//! contracts have admins of their own: the TierVerifier admin can
+//! `update_vkey` or `upgrade`, the ultrahonk-verifier admin can
+//! `set_vk_by_id` or `upgrade`, and the risc0-groth16-verifier admin can
+//! `upgrade`; each changes which proofs the pool accepts. The UltraHonk
+//! backend pins its VK hash, so a re-registered key fails with
+//! `VerifierKeyMismatch` (after renouncing, UltraHonk withdrawals stop
+//! rather than accept it), but no backend can detect a verifier upgrade.
+//! Depositors trust those admins as much as the pool's own.
+//!
+//! ## Token registry
+//!
+//! Only tokens listed by the admin can be deposited. Each listing caps the
+//! size of a single deposit and the total amount of the token held in
+//! escrow. Delisting a token blocks new deposits; existing notes remain
+//! withdrawable.
+//!
+//! ## Protocol fee
+//!
+//! The admin may charge a per-token protocol fee of at most
+//! `MAX_PROTOCOL_FEE_BPS` basis points of each withdrawn amount, sent to
+//! the treasury address. The fee is computed on-chain from the public
+//! amount, so it is not part of the proof: the recipient receives the
+//! amount minus the relayer fee and the protocol fee. Transfers and refunds
+//! are not charged.
+//!
+//! The rate is changed through governance (`SetProtocolFee`), so a raise
+//! is announced `GOVERNANCE_DELAY` ahead. Relayed withdrawals need it: a
+//! relayer fee above the amount minus the protocol fee is rejected with
+//! `InvalidFee`, so a raise can invalidate signed-off withdrawals that
+//! left little room.
+//!
+//! ## Accounting
+//!
+//! The pool keeps running per-token totals of what was deposited and what
+//! left (withdrawals, relayer and protocol fees, refunds). `check_solvency` compares the
+//! outstanding amount with the pool's actual token balance so monitoring
+//! can alert on any divergence.
+//!
+//! ## Emergency pause
+//!
+//! A guardian, appointed through governance (`SetGuardian`), can pause
+//! deposits and withdrawals independently if a circuit or contract bug is
+//! found. A pause lasts at most `MAX_PAUSE_DURATION` and lapses on its own;
+//! the guardian may lift it earlier. A running pause cannot be replaced,
+//! and a scope cannot be paused again until `PAUSE_COOLDOWN` after its last
+//! pause ended, so pauses cannot be chained. `refund` does not depend on
+//! proofs and stays available.
+//!
+//! A withdrawal pause never eats into the governance delay: proposals only
+//! become executable `GOVERNANCE_DELAY` after the pause ends, and
+//! `execute_proposal` fails while withdrawals are paused. `SetGuardian` is
+//! exempt from both, so a guardian that keeps re-pausing cannot defer its
+//! own replacement.
+//!
+//! ## Minimum note age
+//!
+//! A withdrawal right after its deposit links the two by timing, whatever
+//! the size of the anonymity set. The admin may set a minimum note age (at
+//! most `MAX_MIN_NOTE_AGE`): `withdraw` and `transfer` then only accept
+//! roots that became current at least that long ago. A note is only under
+//! roots created after its insertion, so this holds every spent note to
+//! the minimum age without revealing which note it is.
+//!
+//! Only `ROOT_HISTORY_SIZE` roots are kept in the history, so a burst of
+//! inserts could otherwise leave no root old enough. Each commitment set
+//! therefore also keeps a time-based checkpoint outside the history: a
+//! pending root that becomes the checkpoint once it is the minimum age
+//! old, at which point the current root becomes pending. Both are
+//! accepted, so every note is spendable against some root at most about
+//! twice the minimum age after its deposit, however many deposits follow.
+//! `root_checkpoints` lists them and `earliest_withdrawal` tells wallets
+//! when a deposit first becomes spendable.
 //!
 //! ## Cross-contract verification
 //!
-//! Proof verification is delegated to the deployed TierVerifier contract
-//! (CAU7NET7FXSFBBRMLM6X7CJMVAIHMG7RC4YPCXG6G4YOYG6C3CVGR25M on mainnet).
+//! Proof verification is delegated to a verifier contract per proof system
+//! (see `backend`): Groth16 via the deployed TierVerifier contract
+//! (CAU7NET7FXSFBBRMLM6X7CJMVAIHMG7RC4YPCXG6G4YOYG6C3CVGR25M on mainnet),
+//! UltraHonk via ultrahonk-verifier, or RISC Zero receipts via
+//! risc0-groth16-verifier. Each deposit names its proof system, and notes of
+//! different systems form separate commitment sets, so Noir-based notes can
+//! coexist with circom-based ones.
+//!
+//! ## Withdraw statement (v6)
+//!
+//! Private inputs: secret, note_amount, nullifier, Merkle path elements and
+//!                 indices, change secret and nullifier
+//! Public inputs:  [amount, root, nullifier_hash, change_leaf,
+//!                  ext_data_hash, domain]
+//!
+//! The circuit opens the leaf Poseidon(Poseidon(secret, note_amount,
+//! nullifier), note_amount) under root, so note_amount is the amount the
+//! leaf was inserted with. It enforces note_amount = amount + change_amount
+//! (both range checked) and change_leaf = Poseidon(C', change_amount) with
+//! C' = Poseidon(secret', change_amount, nullifier'). change_leaf = 0 is
+//! reserved for full withdrawals and requires change_amount = 0.
+//!
+//! ext_data_hash = sha256(XDR(ExtData { recipient, relayer, fee })) with the
+//! top byte cleared so it is a valid BN254 scalar. The circuit only has to
+//! carry it as a public input; because it is part of the proven statement,
+//! a proof pulled from the mempool cannot be replayed with another payout.
+//!
+//! domain = sha256(XDR(pool address) || network ID) with the top byte
+//! cleared, where the network ID is the sha256 of the network passphrase.
+//! It is carried the same way and ties the proof to one pool deployment on
+//! one network: a proof made for a testnet pool, or for another pool that
+//! shares the verifier key and holds the same commitment, is rejected.
+//!
+//! The Groth16 verifier must be a TierVerifier instance initialized with the
+//! VK of the membership circuit; the v1 kale_tier circuit (`[amount, C]`) is
+//! no longer accepted.
+//!
+//! ## Transfer statement (v2)
+//!
+//! Private inputs: per input note secret, amount, nullifier and Merkle
+//!                 path; per output note secret, amount and nullifier
+//! Public inputs:  [root, nullifier_hash_0, nullifier_hash_1,
+//!                  output_leaf_0, output_leaf_1, outputs_hash, domain]
 //!
-//! ## Circuit mapping (kale_tier circuit reuse)
+//! Input notes are opened as leaves, as in the withdraw statement. The
+//! circuit enforces sum(input amounts) = sum(output amounts), each range
+//! checked, and output_leaf_i = Poseidon(C_i, amount_i) with C_i =
+//! Poseidon(secret_i, amount_i, nullifier_i). Unused slots are 0: an input
+//! with nullifier_hash 0 has amount 0 and no membership check, an output
+//! with leaf 0 has amount 0. outputs_hash = sha256(XDR(outputs)) with the
+//! top byte cleared binds the output memos, so a front-runner cannot strip
+//! them. domain is the same value as in the withdraw statement.
 //!
-//! Private inputs: address_hash=secret, balance=amount, salt=nullifier
-//! Public inputs:  tier_id=amount, commitment_expected=C
+//! Transfer proofs use their own verifier per proof system, set through
+//! governance.
+//!
+//! ## Selective disclosure
+//!
+//! A note owner can prove to an auditor that a given withdrawal spent a
+//! given deposit without publishing the link. `verify_disclosure` is meant
+//! to be simulated, never submitted; it checks a proof of the disclosure
+//! statement
+//!
+//! Public inputs:  [commitment, nullifier_hash, auditor_hash]
+//!
+//! where the circuit enforces commitment = Poseidon(secret, amount,
+//! nullifier) and nullifier_hash = Poseidon(nullifier), and auditor_hash =
+//! sha256(XDR(auditor)) with the top byte cleared. Binding the auditor means
+//! the proof discloses provenance to that auditor only: it does not verify
+//! for anyone else it is forwarded to. Disclosure proofs use their own
+//! verifier per proof system, set through governance.
 //!
 //! ## Disclaimer
 //!
@@ -37,9 +241,13 @@
 
 #![no_std]
 
+mod backend;
+mod merkle;
+mod note;
+
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype,
-    token, Address, BytesN, Env, Symbol, Vec,
+    token, xdr::ToXdr, Address, Bytes, BytesN, Env, Map, Symbol, Vec,
 };
 
 // ============================================================================
@@ -72,9 +280,46 @@ pub enum PoolError {
     NullifierAlreadySpent = 6,
     InvalidProof = 7,
     InvalidAmount = 8,
+    InvalidCommitment = 9,
+    MerkleTreeFull = 10,
+    UnknownRoot = 11,
+    InvalidNullifierHash = 12,
+    InvalidFee = 13,
+    InvalidDenomination = 14,
+    UnsupportedInPoolMode = 15,
+    RefundNotEnabled = 16,
+    RefundLocked = 17,
+    InvalidNoteOpening = 18,
+    ProposalPending = 19,
+    NoPendingProposal = 20,
+    ProposalNotReady = 21,
+    NotGuardian = 22,
+    DepositsPaused = 23,
+    WithdrawalsPaused = 24,
+    InvalidPauseDuration = 25,
+    PauseCoolingDown = 26,
+    TokenNotAllowed = 27,
+    DepositTooLarge = 28,
+    EscrowCapExceeded = 29,
+    MemoTooLong = 30,
+    InvalidBatchSize = 31,
+    VerifierNotConfigured = 32,
+    NullifierNotSpent = 33,
+    InvalidNoteCount = 34,
+    NoteTooYoung = 35,
+    InvalidNoteAge = 36,
+    AdminRenounced = 37,
+    InvalidFeeRate = 38,
+    TreasuryNotSet = 39,
+    /// The UltraHonk verifier's VK no longer hashes to the pinned `vk_hash`
+    VerifierKeyMismatch = 40,
 }
 
-const WITHDRAW_STATEMENT_VERSION: u32 = 1;
+const WITHDRAW_STATEMENT_VERSION: u32 = 6;
+const TRANSFER_STATEMENT_VERSION: u32 = 2;
+
+pub use backend::{ProofSystem, Risc0Backend, UltraHonkBackend, VerifierBackend, WithdrawProof};
+pub use merkle::{Checkpoint, ROOT_HISTORY_SIZE, TREE_DEPTH};
 
 // ============================================================================
 // Data structures
@@ -88,17 +333,229 @@ pub struct DepositRecord {
     pub token: Address,
     /// Amount in stroops
     pub amount: i128,
-    /// Original depositor (event logging only — not used for auth on withdraw)
+    /// Original depositor (not used for auth on withdraw; the only address
+    /// allowed to `refund`)
     pub depositor: Address,
     pub deposited_at: u64,
+    /// Position of the note's leaf, Poseidon(commitment, amount), in its
+    /// commitment set's Merkle tree
+    pub leaf_index: u32,
+    /// Proof system the commitment scheme belongs to
+    pub system: ProofSystem,
+    /// Ledger timestamp after which the depositor may `refund`; None if the
+    /// note was deposited without a refund path
+    pub refund_after: Option<u64>,
+}
+
+/// Payout fields bound into a withdraw proof via `ext_data_hash`.
+///
+/// For a direct withdrawal `relayer` is `None` and `fee` is 0. A relayer
+/// submits the transaction on the recipient's behalf and receives `fee`
+/// out of the note amount; the recipient receives the remainder.
+#[derive(Clone)]
+#[contracttype]
+pub struct ExtData {
+    pub recipient: Address,
+    pub relayer: Option<Address>,
+    pub fee: i128,
+}
+
+/// Minimum delay between proposing and executing a governance action
+/// (48 hours).
+pub const GOVERNANCE_DELAY: u64 = 48 * 60 * 60;
+
+/// A privileged change that must go through the governance delay.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum GovernanceAction {
+    /// Install or replace the verifier of the backend's proof system
+    SetVerifier(VerifierBackend),
+    /// Install or replace the disclosure-proof verifier of the backend's
+    /// proof system
+    SetDisclosureVerifier(VerifierBackend),
+    /// Install or replace the transfer-proof verifier of the backend's
+    /// proof system
+    SetTransferVerifier(VerifierBackend),
+    /// Appoint or replace the guardian
+    SetGuardian(Address),
+    /// Set the protocol fee rate of a token, in basis points
+    SetProtocolFee(Address, u32),
+    Upgrade(BytesN<32>),
+}
+
+impl GovernanceAction {
+    /// Whether a withdrawal pause holds the action back. Replacing the
+    /// guardian gives depositors nothing to exit over, and must not wait on
+    /// pauses of the guardian being replaced.
+    fn waits_for_withdrawals(&self) -> bool {
+        !matches!(self, GovernanceAction::SetGuardian(_))
+    }
+}
+
+/// The pending governance proposal and the earliest time it may execute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Proposal {
+    pub action: GovernanceAction,
+    pub proposed_at: u64,
+    pub executable_at: u64,
+}
+
+/// Largest encrypted memo accepted by `deposit`, in bytes.
+pub const MAX_MEMO_LEN: u32 = 256;
+
+/// Most notes a single `batch_deposit` or `batch_withdraw` may carry. Each
+/// note costs a Merkle insert (~21M CPU instructions) or a pairing check,
+/// so larger batches would exceed the per-transaction CPU limit.
+pub const MAX_BATCH_SIZE: u32 = 4;
+
+/// Most notes a single `transfer` may spend, and most it may create.
+pub const MAX_TRANSFER_NOTES: u32 = 2;
+
+/// A note created by `transfer`.
+#[derive(Clone)]
+#[contracttype]
+pub struct TransferOutput {
+    /// Leaf of the note, Poseidon(commitment, amount)
+    pub leaf: BytesN<32>,
+    /// Encrypted note opening for the payee, as in `deposit`, with the leaf
+    /// as associated data
+    pub memo: Option<Bytes>,
+}
+
+/// One note of a `batch_deposit`; fields as in `deposit`.
+#[derive(Clone)]
+#[contracttype]
+pub struct DepositNote {
+    pub token: Address,
+    pub amount: i128,
+    pub commitment: BytesN<32>,
+    pub system: ProofSystem,
+    pub refund_after: Option<u64>,
+    pub memo: Option<Bytes>,
+}
+
+/// One spend of a `batch_withdraw`; fields as in `withdraw`.
+#[derive(Clone)]
+#[contracttype]
+pub struct Withdrawal {
+    pub token: Address,
+    pub amount: i128,
+    pub root: BytesN<32>,
+    pub proof: WithdrawProof,
+    pub nullifier_hash: BytesN<32>,
+    pub ext_data: ExtData,
+}
+
+/// Admin-set limits for a listed token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TokenConfig {
+    /// Largest amount accepted by a single deposit
+    pub max_deposit: i128,
+    /// Largest total amount of the token the pool may hold in escrow
+    pub escrow_cap: i128,
+}
+
+/// Running totals for one token. outstanding = deposited - withdrawn is
+/// what the pool owes to unspent notes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct TokenTotals {
+    pub deposited: i128,
+    pub withdrawn: i128,
+    pub outstanding: i128,
+    /// Protocol fees sent to the treasury, part of `withdrawn`
+    pub fees: i128,
+}
+
+/// Result of `check_solvency`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Solvency {
+    /// Amount owed to unspent notes
+    pub outstanding: i128,
+    /// Actual token balance of the pool
+    pub balance: i128,
+    /// balance >= outstanding
+    pub solvent: bool,
+}
+
+/// Longest single pause the guardian can impose (7 days).
+pub const MAX_PAUSE_DURATION: u64 = 7 * 24 * 60 * 60;
+
+/// Time after a pause ends before the same scope can be paused again
+/// (48 hours), so pauses cannot be chained.
+pub const PAUSE_COOLDOWN: u64 = GOVERNANCE_DELAY;
+
+/// Highest protocol fee rate the admin can set, in basis points (1%).
+pub const MAX_PROTOCOL_FEE_BPS: u32 = 100;
+
+/// Largest minimum note age the admin can set (24 hours).
+pub const MAX_MIN_NOTE_AGE: u64 = 24 * 60 * 60;
+
+/// Operations the guardian can pause independently.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PauseScope {
+    Deposits,
+    Withdrawals,
+}
+
+/// Ledger timestamps at which the latest pause of each scope ends or ended;
+/// a scope is active again once the ledger timestamp reaches its value
+/// (0 = never paused).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PauseState {
+    pub deposits_paused_until: u64,
+    pub withdrawals_paused_until: u64,
+}
+
+/// Pool configuration chosen at `initialize`. See the module docs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PoolMode {
+    Open,
+    FixedDenomination,
 }
 
 #[contracttype]
 pub enum DataKey {
     Admin,
-    Verifier,
+    /// Verifier backend per proof system
+    Verifier(ProofSystem),
+    /// Disclosure-proof verifier backend per proof system
+    DisclosureVerifier(ProofSystem),
+    /// Transfer-proof verifier backend per proof system
+    TransferVerifier(ProofSystem),
     Deposit(BytesN<32>),
     NullifierUsed(BytesN<32>),
+    /// Incremental Merkle tree of one commitment set:
+    /// (token, denomination, proof system), denomination 0 in `Open` mode
+    Tree((Address, i128, ProofSystem)),
+    Mode,
+    /// Allowed deposit amounts per token in `FixedDenomination` mode
+    Denominations(Address),
+    /// The single pending governance proposal
+    Proposal,
+    Guardian,
+    /// End of the latest pause per scope: its expiry, or the time it was
+    /// lifted
+    PausedUntil(PauseScope),
+    /// Registry entry of a depositable token
+    Token(Address),
+    /// Running accounting totals per token
+    Totals(Address),
+    /// Minimum age, in seconds, of the root a spend proves against
+    MinNoteAge,
+    /// Set once the admin has renounced; never cleared
+    Renounced,
+    /// Recipient of protocol fees
+    Treasury,
+    /// Protocol fee rate per token, in basis points
+    ProtocolFee(Address),
 }
 
 // ============================================================================
@@ -114,37 +571,325 @@ impl CommitmentPool {
     // Admin
     // ========================================================================
 
-    /// Initialize with admin + TierVerifier contract address.
+    /// Initialize with admin + TierVerifier contract address (the `Groth16`
+    /// backend) and the pool mode, which cannot be changed afterwards. Other
+    /// backends are added through governance.
     pub fn initialize(
         env: Env,
         admin: Address,
         verifier: Address,
+        mode: PoolMode,
     ) -> Result<(), PoolError> {
-        if env.storage().instance().has(&DataKey::Admin) {
+        if env.storage().instance().has(&DataKey::Admin)
+            || env.storage().instance().has(&DataKey::Renounced)
+        {
             return Err(PoolError::AlreadyInitialized);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
-        env.storage().instance().set(&DataKey::Verifier, &verifier);
+        env.storage().instance().set(
+            &DataKey::Verifier(ProofSystem::Groth16),
+            &VerifierBackend::Groth16(verifier.clone()),
+        );
+        env.storage().instance().set(&DataKey::Mode, &mode);
         env.events().publish(
             (Symbol::new(&env, "Initialized"),),
-            (admin, verifier),
+            (admin, verifier, mode),
+        );
+        Ok(())
+    }
+
+    /// Allow deposits of exactly `amount` of `token`. Admin only, and only
+    /// in `FixedDenomination` mode.
+    pub fn add_denomination(env: Env, token: Address, amount: i128) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        if Self::mode(&env) != PoolMode::FixedDenomination {
+            return Err(PoolError::UnsupportedInPoolMode);
+        }
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let mut denominations = Self::denominations(env.clone(), token.clone());
+        if denominations.contains(amount) {
+            return Err(PoolError::InvalidDenomination);
+        }
+        denominations.push_back(amount);
+        env.storage()
+            .instance()
+            .set(&DataKey::Denominations(token.clone()), &denominations);
+        env.events().publish(
+            (Symbol::new(&env, "DenominationAdded"),),
+            (token, amount),
+        );
+        Ok(())
+    }
+
+    /// Stop accepting new deposits of `amount` of `token`. Notes already in
+    /// that commitment set remain withdrawable. Admin only.
+    pub fn remove_denomination(env: Env, token: Address, amount: i128) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        let mut denominations = Self::denominations(env.clone(), token.clone());
+        let index = denominations
+            .first_index_of(amount)
+            .ok_or(PoolError::InvalidDenomination)?;
+        denominations.remove(index);
+        env.storage()
+            .instance()
+            .set(&DataKey::Denominations(token.clone()), &denominations);
+        env.events().publish(
+            (Symbol::new(&env, "DenominationRemoved"),),
+            (token, amount),
+        );
+        Ok(())
+    }
+
+    /// Propose a verifier replacement, guardian change, protocol fee change
+    /// or WASM upgrade. Admin only. The action becomes executable once
+    /// withdrawals have been open for `GOVERNANCE_DELAY` (`SetGuardian`:
+    /// `GOVERNANCE_DELAY` from now); fails if another proposal is still
+    /// pending. `SetProtocolFee` is checked here: the rate must be at most
+    /// `MAX_PROTOCOL_FEE_BPS`, and a non-zero rate needs a treasury.
+    pub fn propose(env: Env, action: GovernanceAction) -> Result<Proposal, PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        if env.storage().instance().has(&DataKey::Proposal) {
+            return Err(PoolError::ProposalPending);
+        }
+        if let GovernanceAction::SetProtocolFee(_, fee_bps) = action {
+            if fee_bps > MAX_PROTOCOL_FEE_BPS {
+                return Err(PoolError::InvalidFeeRate);
+            }
+            if fee_bps > 0 && !env.storage().instance().has(&DataKey::Treasury) {
+                return Err(PoolError::TreasuryNotSet);
+            }
+        }
+        let now = env.ledger().timestamp();
+        let start = match action.waits_for_withdrawals() {
+            true => Self::exit_window_start(&env),
+            false => now,
+        };
+        let proposal = Proposal {
+            action,
+            proposed_at: now,
+            executable_at: start + GOVERNANCE_DELAY,
+        };
+        env.storage().instance().set(&DataKey::Proposal, &proposal);
+        env.events().publish(
+            (Symbol::new(&env, "GovernanceProposed"),),
+            (proposal.action.clone(), proposal.executable_at),
+        );
+        Ok(proposal)
+    }
+
+    /// Apply the pending proposal once its delay has elapsed. Admin only;
+    /// fails while withdrawals are paused, except for `SetGuardian`.
+    pub fn execute_proposal(env: Env) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        let proposal: Proposal = env
+            .storage()
+            .instance()
+            .get(&DataKey::Proposal)
+            .ok_or(PoolError::NoPendingProposal)?;
+        if proposal.action.waits_for_withdrawals() && Self::is_paused(&env, PauseScope::Withdrawals) {
+            return Err(PoolError::WithdrawalsPaused);
+        }
+        if env.ledger().timestamp() < proposal.executable_at {
+            return Err(PoolError::ProposalNotReady);
+        }
+        env.storage().instance().remove(&DataKey::Proposal);
+        env.events().publish(
+            (Symbol::new(&env, "GovernanceExecuted"),),
+            (proposal.action.clone(),),
+        );
+        match proposal.action {
+            GovernanceAction::SetVerifier(backend) => {
+                env.storage()
+                    .instance()
+                    .set(&DataKey::Verifier(backend.system()), &backend);
+            }
+            GovernanceAction::SetDisclosureVerifier(backend) => {
+                env.storage()
+                    .instance()
+                    .set(&DataKey::DisclosureVerifier(backend.system()), &backend);
+            }
+            GovernanceAction::SetTransferVerifier(backend) => {
+                env.storage()
+                    .instance()
+                    .set(&DataKey::TransferVerifier(backend.system()), &backend);
+            }
+            GovernanceAction::SetGuardian(guardian) => {
+                env.storage().instance().set(&DataKey::Guardian, &guardian);
+                env.events().publish(
+                    (Symbol::new(&env, "GuardianSet"),),
+                    (guardian,),
+                );
+            }
+            GovernanceAction::SetProtocolFee(token, fee_bps) => {
+                env.storage()
+                    .instance()
+                    .set(&DataKey::ProtocolFee(token.clone()), &fee_bps);
+                env.events().publish(
+                    (Symbol::new(&env, "ProtocolFeeSet"),),
+                    (token, fee_bps),
+                );
+            }
+            GovernanceAction::Upgrade(new_wasm_hash) => {
+                env.deployer().update_current_contract_wasm(new_wasm_hash);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the pending proposal without applying it. Admin only.
+    pub fn cancel_proposal(env: Env) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        let proposal: Proposal = env
+            .storage()
+            .instance()
+            .get(&DataKey::Proposal)
+            .ok_or(PoolError::NoPendingProposal)?;
+        env.storage().instance().remove(&DataKey::Proposal);
+        env.events().publish(
+            (Symbol::new(&env, "GovernanceCancelled"),),
+            (proposal.action,),
+        );
+        Ok(())
+    }
+
+    /// Give up the admin role for good. Admin only; fails while a proposal
+    /// is pending so nothing is left half-applied. Afterwards every admin
+    /// entry point, including `propose` and `execute_proposal`, returns
+    /// `AdminRenounced`. The verifier contracts keep their own admins (see
+    /// the crate docs).
+    pub fn renounce_admin(env: Env) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        if env.storage().instance().has(&DataKey::Proposal) {
+            return Err(PoolError::ProposalPending);
+        }
+        env.storage().instance().remove(&DataKey::Admin);
+        env.storage().instance().set(&DataKey::Renounced, &true);
+        env.events().publish(
+            (Symbol::new(&env, "AdminRenounced"),),
+            (admin, env.ledger().timestamp()),
+        );
+        Ok(())
+    }
+
+    /// List `token` for deposits, or update its limits. Admin only.
+    pub fn set_token_config(env: Env, token: Address, config: TokenConfig) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        if config.max_deposit <= 0 || config.escrow_cap <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Token(token.clone()), &config);
+        env.events().publish(
+            (Symbol::new(&env, "TokenConfigured"),),
+            (token, config.max_deposit, config.escrow_cap),
+        );
+        Ok(())
+    }
+
+    /// Delist `token`. New deposits fail; existing notes can still be
+    /// withdrawn. Admin only.
+    pub fn remove_token(env: Env, token: Address) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+        admin.require_auth();
+        if !env.storage().instance().has(&DataKey::Token(token.clone())) {
+            return Err(PoolError::TokenNotAllowed);
+        }
+        env.storage().instance().remove(&DataKey::Token(token.clone()));
+        env.events().publish(
+            (Symbol::new(&env, "TokenRemoved"),),
+            (token,),
         );
         Ok(())
     }
 
-    /// Replace the verifier contract address. Admin only.
-    pub fn set_verifier(env: Env, verifier: Address) -> Result<(), PoolError> {
+    /// Set the address protocol fees are paid to. Admin only.
+    pub fn set_treasury(env: Env, treasury: Address) -> Result<(), PoolError> {
         let admin = Self::require_admin(&env)?;
         admin.require_auth();
-        env.storage().instance().set(&DataKey::Verifier, &verifier);
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+        env.events().publish(
+            (Symbol::new(&env, "TreasurySet"),),
+            (treasury,),
+        );
+        Ok(())
+    }
+
+    /// Pause `scope` for `duration` seconds (at most `MAX_PAUSE_DURATION`).
+    /// Guardian only. Fails while the scope is paused and for
+    /// `PAUSE_COOLDOWN` after its last pause ended. Pausing withdrawals
+    /// pushes a pending proposal other than `SetGuardian` back to
+    /// `GOVERNANCE_DELAY` after the pause.
+    pub fn pause(env: Env, scope: PauseScope, duration: u64) -> Result<u64, PoolError> {
+        let guardian = Self::require_guardian(&env)?;
+        guardian.require_auth();
+        if duration == 0 || duration > MAX_PAUSE_DURATION {
+            return Err(PoolError::InvalidPauseDuration);
+        }
+        let now = env.ledger().timestamp();
+        let last_end = Self::paused_until(&env, scope);
+        if last_end != 0 && now < last_end + PAUSE_COOLDOWN {
+            return Err(PoolError::PauseCoolingDown);
+        }
+        let until = now + duration;
+        env.storage().instance().set(&DataKey::PausedUntil(scope), &until);
+        if scope == PauseScope::Withdrawals {
+            let proposal: Option<Proposal> = env.storage().instance().get(&DataKey::Proposal);
+            if let Some(mut proposal) = proposal.filter(|p| p.action.waits_for_withdrawals()) {
+                proposal.executable_at = proposal.executable_at.max(until + GOVERNANCE_DELAY);
+                env.storage().instance().set(&DataKey::Proposal, &proposal);
+                env.events().publish(
+                    (Symbol::new(&env, "GovernanceDelayed"),),
+                    (proposal.action, proposal.executable_at),
+                );
+            }
+        }
+        env.events().publish(
+            (Symbol::new(&env, "Paused"),),
+            (scope, until),
+        );
+        Ok(until)
+    }
+
+    /// Lift a pause before it expires. Guardian only. The cooldown starts
+    /// now; a pending proposal keeps its delayed `executable_at`.
+    pub fn unpause(env: Env, scope: PauseScope) -> Result<(), PoolError> {
+        let guardian = Self::require_guardian(&env)?;
+        guardian.require_auth();
+        if Self::is_paused(&env, scope) {
+            env.storage()
+                .instance()
+                .set(&DataKey::PausedUntil(scope), &env.ledger().timestamp());
+        }
+        env.events().publish(
+            (Symbol::new(&env, "Unpaused"),),
+            (scope,),
+        );
         Ok(())
     }
 
-    /// Upgrade this contract WASM. Admin only.
-    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), PoolError> {
+    /// Require spends to prove against roots at least `age` seconds old
+    /// (at most `MAX_MIN_NOTE_AGE`; 0 disables the policy). Admin only.
+    pub fn set_min_note_age(env: Env, age: u64) -> Result<(), PoolError> {
         let admin = Self::require_admin(&env)?;
         admin.require_auth();
-        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        if age > MAX_MIN_NOTE_AGE {
+            return Err(PoolError::InvalidNoteAge);
+        }
+        env.storage().instance().set(&DataKey::MinNoteAge, &age);
+        env.events().publish(
+            (Symbol::new(&env, "MinNoteAgeSet"),),
+            (age,),
+        );
         Ok(())
     }
 
@@ -162,23 +907,44 @@ impl CommitmentPool {
     ///
     /// commitment = 32-byte big-endian encoding of
     ///   Poseidon(secret_bigint, amount_bigint, nullifier_bigint)
-    /// computed client-side by generateCommitmentKey().
+    /// computed client-side by generateCommitmentKey(). It must be a
+    /// canonical BN254 scalar.
+    ///
+    /// In `FixedDenomination` mode `amount` must be a registered
+    /// denomination of `token`.
+    ///
+    /// `system` is the proof system of the note's commitment scheme; the
+    /// note joins that system's commitment set and can only be spent with a
+    /// proof of that system. Its verifier must be configured.
+    ///
+    /// `refund_after` opts the note into `refund` once the ledger timestamp
+    /// passes it. Leave it None to keep the deposit unlinkable forever.
+    ///
+    /// `memo` is an optional note ciphertext (at most `MAX_MEMO_LEN` bytes)
+    /// for the note's recipient; it is only emitted, never stored.
+    ///
+    /// Returns the leaf index assigned in the commitment set's Merkle tree.
+    #[allow(clippy::too_many_arguments)]
     pub fn deposit(
         env: Env,
         depositor: Address,
         token: Address,
         amount: i128,
         commitment: BytesN<32>,
-    ) -> Result<(), PoolError> {
-        if amount <= 0 {
-            return Err(PoolError::InvalidAmount);
-        }
-
+        system: ProofSystem,
+        refund_after: Option<u64>,
+        memo: Option<Bytes>,
+    ) -> Result<u32, PoolError> {
         depositor.require_auth();
-
-        if env.storage().persistent().has(&DataKey::Deposit(commitment.clone())) {
-            return Err(PoolError::CommitmentAlreadyDeposited);
-        }
+        let note = DepositNote {
+            token: token.clone(),
+            amount,
+            commitment,
+            system,
+            refund_after,
+            memo,
+        };
+        let leaf_index = Self::record_deposit(&env, &depositor, note)?;
 
         // Pull funds into escrow
         token::Client::new(&env, &token).transfer(
@@ -187,27 +953,88 @@ impl CommitmentPool {
             &amount,
         );
 
-        let record = DepositRecord {
-            commitment: commitment.clone(),
-            token: token.clone(),
-            amount,
-            depositor: depositor.clone(),
-            deposited_at: env.ledger().timestamp(),
-        };
+        Ok(leaf_index)
+    }
 
-        let max_ttl = env.storage().max_ttl();
-        env.storage()
+    /// Deposit several notes with one authorization. Each note is checked
+    /// and recorded exactly as by `deposit` and emits its own `Deposited`
+    /// event; the funds are then pulled with one transfer per token. At most
+    /// `MAX_BATCH_SIZE` notes. Returns the leaf indices in input order.
+    pub fn batch_deposit(
+        env: Env,
+        depositor: Address,
+        notes: Vec<DepositNote>,
+    ) -> Result<Vec<u32>, PoolError> {
+        Self::check_batch_size(notes.len())?;
+        depositor.require_auth();
+
+        let mut totals: Map<Address, i128> = Map::new(&env);
+        let mut leaf_indices = Vec::new(&env);
+        for note in notes.iter() {
+            let token = note.token.clone();
+            let amount = note.amount;
+            leaf_indices.push_back(Self::record_deposit(&env, &depositor, note)?);
+            totals.set(token.clone(), totals.get(token).unwrap_or(0) + amount);
+        }
+
+        for (token, total) in totals.iter() {
+            token::Client::new(&env, &token).transfer(
+                &depositor,
+                env.current_contract_address(),
+                &total,
+            );
+        }
+
+        Ok(leaf_indices)
+    }
+
+    /// Reclaim a deposit whose refund timestamp has passed.
+    ///
+    /// The depositor reveals the note opening; the contract checks that
+    /// Poseidon(secret, amount, nullifier) equals `commitment`, marks
+    /// Poseidon(nullifier) spent so the note can no longer be withdrawn, and
+    /// returns the full amount to the depositor. Change notes have no
+    /// deposit record and cannot be refunded.
+    pub fn refund(
+        env: Env,
+        commitment: BytesN<32>,
+        secret: BytesN<32>,
+        nullifier: BytesN<32>,
+    ) -> Result<(), PoolError> {
+        let record: DepositRecord = env
+            .storage()
             .persistent()
-            .set(&DataKey::Deposit(commitment.clone()), &record);
-        env.storage().persistent().extend_ttl(
-            &DataKey::Deposit(commitment.clone()),
-            max_ttl,
-            max_ttl,
+            .get(&DataKey::Deposit(commitment.clone()))
+            .ok_or(PoolError::CommitmentNotFound)?;
+        let refund_after = record.refund_after.ok_or(PoolError::RefundNotEnabled)?;
+        if env.ledger().timestamp() < refund_after {
+            return Err(PoolError::RefundLocked);
+        }
+
+        record.depositor.require_auth();
+
+        if !merkle::is_field_element(&secret) || !merkle::is_field_element(&nullifier) {
+            return Err(PoolError::InvalidNoteOpening);
+        }
+        let amount = Self::i128_to_bytes32(&env, record.amount);
+        if note::commitment(&env, &secret, &amount, &nullifier) != commitment {
+            return Err(PoolError::InvalidNoteOpening);
+        }
+
+        let nullifier_hash = note::nullifier_hash(&env, &nullifier);
+        Self::require_unspent(&env, &nullifier_hash)?;
+        Self::mark_spent(&env, &nullifier_hash);
+
+        Self::record_outflow(&env, &record.token, record.amount);
+        token::Client::new(&env, &record.token).transfer(
+            &env.current_contract_address(),
+            &record.depositor,
+            &record.amount,
         );
 
         env.events().publish(
-            (Symbol::new(&env, "Deposited"),),
-            (commitment, amount, token, depositor),
+            (Symbol::new(&env, "Refunded"),),
+            (commitment, record.depositor, record.amount, record.token, nullifier_hash),
         );
 
         Ok(())
@@ -219,95 +1046,196 @@ impl CommitmentPool {
 
     /// Withdraw escrowed tokens to any recipient by presenting a valid ZK proof.
     ///
-    /// The caller proves knowledge of (secret, nullifier) whose Poseidon hash
-    /// equals the on-chain commitment, without revealing secret or nullifier.
+    /// The caller proves that some leaf of the commitment tree with root
+    /// `root` is Poseidon(Poseidon(secret, amount, nullifier), amount),
+    /// without revealing the leaf, secret or nullifier. `root` must be one
+    /// of the last `ROOT_HISTORY_SIZE` roots, or a checkpoint root, of the
+    /// tree for `token` (and, in `FixedDenomination` mode, the denomination
+    /// `amount`).
+    ///
+    /// nullifier_hash = Poseidon(nullifier) as a 32-byte big-endian scalar.
+    /// It is a public input of the proof, so it cannot be chosen freely by
+    /// the caller, and it is stored after withdrawal to prevent
+    /// double-spending the same note.
     ///
-    /// nullifier_hash = sha256(nullifier_hex_string) as 32 bytes.
-    /// It is stored after withdrawal to prevent double-spending the same key.
+    /// `ext_data` names the recipient and, for relayed withdrawals, the
+    /// relayer and its fee (0 <= fee <= amount). All three are bound by the
+    /// proof, so neither a front-runner nor the relayer can alter them. No
+    /// auth is required: whoever submits the proof only pays the network fee.
     ///
     /// On-chain proof verification is performed by the TierVerifier contract
     /// using Stellar Protocol 25 BN254 host functions.
     pub fn withdraw(
         env: Env,
-        commitment: BytesN<32>,
-        proof: Groth16Proof,
+        token: Address,
+        amount: i128,
+        root: BytesN<32>,
+        proof: WithdrawProof,
         nullifier_hash: BytesN<32>,
-        recipient: Address,
+        ext_data: ExtData,
     ) -> Result<(), PoolError> {
-        // 1. Load deposit
-        let record: DepositRecord = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Deposit(commitment.clone()))
-            .ok_or(PoolError::CommitmentNotFound)?;
+        let w = Withdrawal {
+            token,
+            amount,
+            root,
+            proof,
+            nullifier_hash,
+            ext_data,
+        };
+        Self::spend(&env, w, None)?;
+        Ok(())
+    }
 
-        // 2. Nullifier double-spend check
-        if env
-            .storage()
-            .persistent()
-            .get::<DataKey, bool>(&DataKey::NullifierUsed(nullifier_hash.clone()))
-            .unwrap_or(false)
+    /// Join-split withdrawal: spend one note, pay out `amount` of it and
+    /// append `change_leaf`, the leaf of a fresh note for the remainder, to
+    /// the token's tree in the same call.
+    ///
+    /// The proof shows the spent note's amount equals `amount` plus the
+    /// change note's amount, so the spent amount itself stays private.
+    /// Payout and `ext_data` rules are the same as `withdraw`. Returns the
+    /// leaf index of the change note. Only available in `Open` mode.
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw_with_change(
+        env: Env,
+        token: Address,
+        amount: i128,
+        root: BytesN<32>,
+        proof: WithdrawProof,
+        nullifier_hash: BytesN<32>,
+        change_leaf: BytesN<32>,
+        ext_data: ExtData,
+    ) -> Result<u32, PoolError> {
+        if Self::mode(&env) != PoolMode::Open {
+            return Err(PoolError::UnsupportedInPoolMode);
+        }
+        let w = Withdrawal {
+            token,
+            amount,
+            root,
+            proof,
+            nullifier_hash,
+            ext_data,
+        };
+        let change_index = Self::spend(&env, w, Some(change_leaf))?;
+        Ok(change_index.unwrap())
+    }
+
+    /// Process several full withdrawals atomically: if any spend fails,
+    /// none is applied. Each spend follows the rules of `withdraw` and emits
+    /// its own `Withdrawn` event. At most `MAX_BATCH_SIZE` spends.
+    pub fn batch_withdraw(env: Env, withdrawals: Vec<Withdrawal>) -> Result<(), PoolError> {
+        Self::check_batch_size(withdrawals.len())?;
+        for w in withdrawals.iter() {
+            Self::spend(&env, w, None)?;
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // Transfer
+    // ========================================================================
+
+    /// Private payment inside the pool: spend up to `MAX_TRANSFER_NOTES`
+    /// notes of `token` and append the leaves of up to `MAX_TRANSFER_NOTES`
+    /// new notes of equal total value to the same commitment set.
+    ///
+    /// The proof (see "Transfer statement" in the crate docs) shows every
+    /// nullifier hash belongs to a leaf under `root` and that the outputs
+    /// add up to the inputs; no amount is revealed. Each output's memo is
+    /// emitted with its `TransferNote` event so the payee can find it.
+    /// Escrow totals do not change. Pauses with withdrawals. Returns the
+    /// leaf indices of the outputs in input order. Only available in `Open`
+    /// mode.
+    pub fn transfer(
+        env: Env,
+        token: Address,
+        root: BytesN<32>,
+        proof: WithdrawProof,
+        nullifier_hashes: Vec<BytesN<32>>,
+        outputs: Vec<TransferOutput>,
+    ) -> Result<Vec<u32>, PoolError> {
+        if Self::mode(&env) != PoolMode::Open {
+            return Err(PoolError::UnsupportedInPoolMode);
+        }
+        if Self::is_paused(&env, PauseScope::Withdrawals) {
+            return Err(PoolError::WithdrawalsPaused);
+        }
+        if nullifier_hashes.is_empty()
+            || nullifier_hashes.len() > MAX_TRANSFER_NOTES
+            || outputs.is_empty()
+            || outputs.len() > MAX_TRANSFER_NOTES
         {
-            return Err(PoolError::NullifierAlreadySpent);
+            return Err(PoolError::InvalidNoteCount);
         }
 
-        // 3. Verify Groth16 proof on-chain via TierVerifier.
-        //
-        //    Public inputs:
-        //      [0] tier_id = amount as 32-byte big-endian U256
-        //      [1] commitment_expected = the stored commitment
-        //
-        //    Private inputs (committed to by the proof, not revealed):
-        //      address_hash = secret
-        //      balance      = amount
-        //      salt         = nullifier
-        let verifier: Address = env
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        // 0 is the "unused slot" sentinel of the statement.
+        for (i, nullifier_hash) in nullifier_hashes.iter().enumerate() {
+            if !merkle::is_field_element(&nullifier_hash) || nullifier_hash == zero {
+                return Err(PoolError::InvalidNullifierHash);
+            }
+            if nullifier_hashes.first_index_of(&nullifier_hash) != Some(i as u32) {
+                return Err(PoolError::NullifierAlreadySpent);
+            }
+        }
+        let mut leaves: Vec<BytesN<32>> = Vec::new(&env);
+        for output in outputs.iter() {
+            if !merkle::is_field_element(&output.leaf) || output.leaf == zero {
+                return Err(PoolError::InvalidCommitment);
+            }
+            if leaves.contains(&output.leaf) {
+                return Err(PoolError::CommitmentAlreadyDeposited);
+            }
+            if output.memo.as_ref().is_some_and(|m| m.len() > MAX_MEMO_LEN) {
+                return Err(PoolError::MemoTooLong);
+            }
+            leaves.push_back(output.leaf);
+        }
+
+        let set = (token.clone(), 0, proof.system());
+        Self::require_known_root(&env, &set, &root)?;
+        for nullifier_hash in nullifier_hashes.iter() {
+            Self::require_unspent(&env, &nullifier_hash)?;
+        }
+
+        let backend: VerifierBackend = env
             .storage()
             .instance()
-            .get(&DataKey::Verifier)
-            .ok_or(PoolError::NotInitialized)?;
+            .get(&DataKey::TransferVerifier(proof.system()))
+            .ok_or(PoolError::VerifierNotConfigured)?;
 
-        let amount_bytes = Self::i128_to_bytes32(&env, record.amount);
         let mut public_inputs: Vec<BytesN<32>> = Vec::new(&env);
-        public_inputs.push_back(amount_bytes);
-        public_inputs.push_back(commitment.clone());
-
-        let verifier_client = verifier::Client::new(&env, &verifier);
-        verifier_client
-            .try_verify_groth16(&public_inputs, &proof)
-            .map_err(|_| PoolError::InvalidProof)?
-            .map_err(|_| PoolError::InvalidProof)?;
+        public_inputs.push_back(root.clone());
+        for i in 0..MAX_TRANSFER_NOTES {
+            public_inputs.push_back(nullifier_hashes.get(i).unwrap_or_else(|| zero.clone()));
+        }
+        for i in 0..MAX_TRANSFER_NOTES {
+            public_inputs.push_back(leaves.get(i).unwrap_or_else(|| zero.clone()));
+        }
+        public_inputs.push_back(Self::hash_transfer_outputs(&env, &outputs));
+        public_inputs.push_back(Self::domain(env.clone()));
 
-        // 4. Mark nullifier spent
-        let max_ttl = env.storage().max_ttl();
-        env.storage()
-            .persistent()
-            .set(&DataKey::NullifierUsed(nullifier_hash.clone()), &true);
-        env.storage().persistent().extend_ttl(
-            &DataKey::NullifierUsed(nullifier_hash.clone()),
-            max_ttl,
-            max_ttl,
-        );
+        backend::verify(&env, &backend, &public_inputs, &proof)?;
 
-        // 5. Release funds to recipient (specified by the prover, not the depositor)
-        token::Client::new(&env, &record.token).transfer(
-            &env.current_contract_address(),
-            &recipient,
-            &record.amount,
-        );
+        for nullifier_hash in nullifier_hashes.iter() {
+            Self::mark_spent(&env, &nullifier_hash);
+        }
+        let mut leaf_indices = Vec::new(&env);
+        for output in outputs.iter() {
+            let (leaf_index, new_root) = Self::insert_leaf(&env, &set, &output.leaf)?;
+            env.events().publish(
+                (Symbol::new(&env, "TransferNote"),),
+                (output.leaf, token.clone(), leaf_index, new_root, output.memo),
+            );
+            leaf_indices.push_back(leaf_index);
+        }
 
         env.events().publish(
-            (Symbol::new(&env, "Withdrawn"),),
-            (
-                commitment,
-                recipient,
-                nullifier_hash,
-                record.amount,
-                WITHDRAW_STATEMENT_VERSION,
-            ),
+            (Symbol::new(&env, "Transferred"),),
+            (root, nullifier_hashes, token, proof.system(), TRANSFER_STATEMENT_VERSION),
         );
 
-        Ok(())
+        Ok(leaf_indices)
     }
 
     // ========================================================================
@@ -320,6 +1248,64 @@ impl CommitmentPool {
             .get(&DataKey::Deposit(commitment))
     }
 
+    /// Latest root of the (token, denomination, system) commitment tree
+    /// (the empty-tree root if nothing has been deposited yet). Pass
+    /// denomination 0 in `Open` mode.
+    pub fn get_root(env: Env, token: Address, denomination: i128, system: ProofSystem) -> BytesN<32> {
+        Self::load_tree(&env, &(token, denomination, system)).root()
+    }
+
+    /// True if `root` is within the accepted root history, or is a
+    /// checkpoint root, of the (token, denomination, system) commitment set.
+    pub fn is_known_root(
+        env: Env,
+        token: Address,
+        denomination: i128,
+        system: ProofSystem,
+        root: BytesN<32>,
+    ) -> bool {
+        Self::load_tree(&env, &(token, denomination, system)).is_known_root(&root)
+    }
+
+    /// The checkpoint and pending roots of the (token, denomination,
+    /// system) commitment set, in that order. A wallet whose note is no
+    /// longer under a history root proves against the first `size` leaves.
+    pub fn root_checkpoints(
+        env: Env,
+        token: Address,
+        denomination: i128,
+        system: ProofSystem,
+    ) -> Vec<Checkpoint> {
+        let tree = Self::load_tree(&env, &(token, denomination, system));
+        Vec::from_array(&env, [tree.checkpoint, tree.pending])
+    }
+
+    /// Number of commitments inserted into the (token, denomination, system)
+    /// commitment set so far.
+    pub fn commitment_count(env: Env, token: Address, denomination: i128, system: ProofSystem) -> u32 {
+        Self::load_tree(&env, &(token, denomination, system)).next_index
+    }
+
+    /// The pending governance proposal, if any.
+    pub fn pending_proposal(env: Env) -> Option<Proposal> {
+        env.storage().instance().get(&DataKey::Proposal)
+    }
+
+    pub fn pool_mode(env: Env) -> Result<PoolMode, PoolError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Mode)
+            .ok_or(PoolError::NotInitialized)
+    }
+
+    /// Deposit amounts accepted for `token` in `FixedDenomination` mode.
+    pub fn denominations(env: Env, token: Address) -> Vec<i128> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Denominations(token))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
     pub fn is_nullifier_used(env: Env, nullifier_hash: BytesN<32>) -> bool {
         env.storage()
             .persistent()
@@ -327,18 +1313,175 @@ impl CommitmentPool {
             .unwrap_or(false)
     }
 
+    /// The admin, or `AdminRenounced` once the pool is immutable.
     pub fn admin(env: Env) -> Result<Address, PoolError> {
+        if Self::is_immutable(env.clone()) {
+            return Err(PoolError::AdminRenounced);
+        }
         env.storage()
             .instance()
             .get(&DataKey::Admin)
             .ok_or(PoolError::NotInitialized)
     }
 
-    pub fn verifier(env: Env) -> Result<Address, PoolError> {
+    /// True once the admin has renounced: no governance action can run.
+    pub fn is_immutable(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::Renounced)
+    }
+
+    /// Registry entry of `token`, or None if it is not listed.
+    pub fn token_config(env: Env, token: Address) -> Option<TokenConfig> {
+        env.storage().instance().get(&DataKey::Token(token))
+    }
+
+    /// Amount of `token` currently held in escrow for unspent notes.
+    pub fn escrow(env: Env, token: Address) -> i128 {
+        Self::totals(env, token).outstanding
+    }
+
+    /// Running deposited / withdrawn / outstanding totals of `token`.
+    pub fn totals(env: Env, token: Address) -> TokenTotals {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Totals(token))
+            .unwrap_or_default()
+    }
+
+    /// Protocol fees of `token` sent to the treasury so far.
+    pub fn fees_collected(env: Env, token: Address) -> i128 {
+        Self::totals(env, token).fees
+    }
+
+    /// Protocol fee rate of `token` in basis points.
+    pub fn protocol_fee(env: Env, token: Address) -> u32 {
         env.storage()
             .instance()
-            .get(&DataKey::Verifier)
-            .ok_or(PoolError::NotInitialized)
+            .get(&DataKey::ProtocolFee(token))
+            .unwrap_or(0)
+    }
+
+    pub fn treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Treasury)
+    }
+
+    /// Compare what the pool owes for `token` with what it actually holds.
+    pub fn check_solvency(env: Env, token: Address) -> Solvency {
+        let outstanding = Self::totals(env.clone(), token.clone()).outstanding;
+        let balance = token::Client::new(&env, &token).balance(&env.current_contract_address());
+        Solvency {
+            outstanding,
+            balance,
+            solvent: balance >= outstanding,
+        }
+    }
+
+    /// Minimum age, in seconds, of the root a spend proves against.
+    pub fn min_note_age(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::MinNoteAge).unwrap_or(0)
+    }
+
+    /// Earliest ledger timestamp at which the deposit of `commitment` can
+    /// be withdrawn or transferred under the current minimum note age, or
+    /// None for an unknown commitment: the time the oldest kept root that
+    /// covers the note is the minimum age old. A later burst of deposits
+    /// may evict a history root before then; checkpoint roots (see
+    /// `root_checkpoints`) are never evicted early.
+    pub fn earliest_withdrawal(env: Env, commitment: BytesN<32>) -> Option<u64> {
+        let record = Self::get_deposit(env.clone(), commitment)?;
+        let set = (
+            record.token,
+            Self::set_denomination(&env, record.amount),
+            record.system,
+        );
+        let root_time = Self::load_tree(&env, &set).first_root_time_covering(record.leaf_index);
+        Some(root_time + Self::min_note_age(env))
+    }
+
+    pub fn guardian(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Guardian)
+    }
+
+    /// Current pause expiries; compare with the ledger timestamp to tell
+    /// whether a scope is paused.
+    pub fn pause_state(env: Env) -> PauseState {
+        PauseState {
+            deposits_paused_until: Self::paused_until(&env, PauseScope::Deposits),
+            withdrawals_paused_until: Self::paused_until(&env, PauseScope::Withdrawals),
+        }
+    }
+
+    /// Verifier backend of `system`, or None if that system is not enabled.
+    pub fn verifier(env: Env, system: ProofSystem) -> Option<VerifierBackend> {
+        env.storage().instance().get(&DataKey::Verifier(system))
+    }
+
+    /// Check a selective-disclosure proof that the deposit of `commitment`
+    /// was spent under `nullifier_hash`, addressed to `auditor`. Intended
+    /// to be run as a simulation by the auditor; returns true or an error.
+    pub fn verify_disclosure(
+        env: Env,
+        commitment: BytesN<32>,
+        nullifier_hash: BytesN<32>,
+        auditor: Address,
+        proof: WithdrawProof,
+    ) -> Result<bool, PoolError> {
+        let record: DepositRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Deposit(commitment.clone()))
+            .ok_or(PoolError::CommitmentNotFound)?;
+        if !Self::is_nullifier_used(env.clone(), nullifier_hash.clone()) {
+            return Err(PoolError::NullifierNotSpent);
+        }
+        if record.system != proof.system() {
+            return Err(PoolError::InvalidProof);
+        }
+        let backend: VerifierBackend = env
+            .storage()
+            .instance()
+            .get(&DataKey::DisclosureVerifier(record.system))
+            .ok_or(PoolError::VerifierNotConfigured)?;
+
+        let public_inputs = Vec::from_array(
+            &env,
+            [commitment, nullifier_hash, Self::auditor_hash(env.clone(), auditor)],
+        );
+        backend::verify(&env, &backend, &public_inputs, &proof)?;
+        Ok(true)
+    }
+
+    /// The `auditor_hash` public input of a disclosure proof for `auditor`.
+    pub fn auditor_hash(env: Env, auditor: Address) -> BytesN<32> {
+        Self::field_hash(&env, &auditor.to_xdr(&env))
+    }
+
+    /// Disclosure-proof verifier backend of `system`, if configured.
+    pub fn disclosure_verifier(env: Env, system: ProofSystem) -> Option<VerifierBackend> {
+        env.storage().instance().get(&DataKey::DisclosureVerifier(system))
+    }
+
+    /// Transfer-proof verifier backend of `system`, if configured.
+    pub fn transfer_verifier(env: Env, system: ProofSystem) -> Option<VerifierBackend> {
+        env.storage().instance().get(&DataKey::TransferVerifier(system))
+    }
+
+    /// The `outputs_hash` public input a transfer proof must commit to.
+    pub fn transfer_outputs_hash(env: Env, outputs: Vec<TransferOutput>) -> BytesN<32> {
+        Self::hash_transfer_outputs(&env, &outputs)
+    }
+
+    /// The `ext_data_hash` public input a withdraw proof must commit to for
+    /// the given payout. Exposed so provers can match the on-chain encoding.
+    pub fn ext_data_hash(env: Env, ext_data: ExtData) -> BytesN<32> {
+        Self::hash_ext_data(&env, &ext_data)
+    }
+
+    /// The `domain` public input of withdraw and transfer proofs for this
+    /// pool on the current network.
+    pub fn domain(env: Env) -> BytesN<32> {
+        let mut data = env.current_contract_address().to_xdr(&env);
+        data.append(&env.ledger().network_id().into());
+        Self::field_hash(&env, &data)
     }
 
     /// Version marker for the current withdraw statement schema.
@@ -346,403 +1489,2768 @@ impl CommitmentPool {
         WITHDRAW_STATEMENT_VERSION
     }
 
+    /// Version marker for the current transfer statement schema.
+    pub fn transfer_statement_version(_env: Env) -> u32 {
+        TRANSFER_STATEMENT_VERSION
+    }
+
     // ========================================================================
     // Internals
     // ========================================================================
 
-    fn require_admin(env: &Env) -> Result<Address, PoolError> {
-        env.storage()
+    /// Shared path of `withdraw` and `withdraw_with_change`. Returns the
+    /// change note's leaf index when one was inserted.
+    fn spend(
+        env: &Env,
+        w: Withdrawal,
+        change_leaf: Option<BytesN<32>>,
+    ) -> Result<Option<u32>, PoolError> {
+        let Withdrawal {
+            token,
+            amount,
+            root,
+            proof,
+            nullifier_hash,
+            ext_data,
+        } = w;
+        if Self::is_paused(env, PauseScope::Withdrawals) {
+            return Err(PoolError::WithdrawalsPaused);
+        }
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let protocol_fee = Self::protocol_fee_for(env, &token, amount);
+        let treasury = match protocol_fee {
+            0 => None,
+            _ => Some(Self::treasury(env.clone()).ok_or(PoolError::TreasuryNotSet)?),
+        };
+        if ext_data.fee < 0
+            || ext_data.fee > amount - protocol_fee
+            || (ext_data.fee > 0 && ext_data.relayer.is_none())
+        {
+            return Err(PoolError::InvalidFee);
+        }
+        // A non-canonical encoding (N + p) would reduce to the same scalar
+        // inside the verifier but miss the spent-set lookup below.
+        if !merkle::is_field_element(&nullifier_hash) {
+            return Err(PoolError::InvalidNullifierHash);
+        }
+        // 0 is the "no change" sentinel of the statement.
+        if let Some(change) = &change_leaf {
+            if !merkle::is_field_element(change) || change.to_array() == [0u8; 32] {
+                return Err(PoolError::InvalidCommitment);
+            }
+        }
+
+        // 1. Root must be a recent root of the note's commitment set, old
+        //    enough under the minimum note age
+        let set = (token.clone(), Self::set_denomination(env, amount), proof.system());
+        Self::require_known_root(env, &set, &root)?;
+
+        // 2. Nullifier double-spend check
+        Self::require_unspent(env, &nullifier_hash)?;
+
+        // 3. Verify the proof on-chain with the backend of its proof system.
+        //
+        //    Public inputs:
+        //      [0] amount paid out, as 32-byte big-endian U256
+        //      [1] root of the commitment tree
+        //      [2] nullifier_hash = Poseidon(nullifier)
+        //      [3] change_leaf, or 0 for a full withdrawal
+        //      [4] ext_data_hash  = field-truncated sha256 of the payout fields
+        //      [5] domain         = field-truncated sha256 of pool address
+        //                           and network ID
+        //
+        //    Private inputs (committed to by the proof, not revealed):
+        //      secret, note_amount, nullifier, path_elements[TREE_DEPTH],
+        //      path_indices[TREE_DEPTH], change secret and nullifier
+        let backend: VerifierBackend = env
+            .storage()
             .instance()
-            .get(&DataKey::Admin)
-            .ok_or(PoolError::NotAdmin)
-    }
+            .get(&DataKey::Verifier(proof.system()))
+            .ok_or(PoolError::VerifierNotConfigured)?;
 
-    /// Encode an i128 as a 32-byte big-endian value (high 16 bytes = 0).
-    pub fn i128_to_bytes32(env: &Env, value: i128) -> BytesN<32> {
-        let mut arr = [0u8; 32];
-        let be = value.to_be_bytes();
-        arr[16..32].copy_from_slice(&be);
-        BytesN::from_array(env, &arr)
+        let amount_bytes = Self::i128_to_bytes32(env, amount);
+        let mut public_inputs: Vec<BytesN<32>> = Vec::new(env);
+        public_inputs.push_back(amount_bytes);
+        public_inputs.push_back(root.clone());
+        public_inputs.push_back(nullifier_hash.clone());
+        public_inputs.push_back(
+            change_leaf
+                .clone()
+                .unwrap_or_else(|| BytesN::from_array(env, &[0u8; 32])),
+        );
+        public_inputs.push_back(Self::hash_ext_data(env, &ext_data));
+        public_inputs.push_back(Self::domain(env.clone()));
+
+        backend::verify(env, &backend, &public_inputs, &proof)?;
+
+        // 4. Mark nullifier spent, then append the change note (if any)
+        Self::mark_spent(env, &nullifier_hash);
+
+        let change_index = match change_leaf {
+            Some(change) => {
+                let (leaf_index, new_root) =
+                    Self::insert_leaf(env, &set, &change)?;
+                env.events().publish(
+                    (Symbol::new(env, "ChangeNote"),),
+                    (change, token.clone(), leaf_index, new_root),
+                );
+                Some(leaf_index)
+            }
+            None => None,
+        };
+
+        // 5. Release funds to recipient (specified by the prover, not the
+        //    depositor), minus the relayer fee if one is bound and the
+        //    protocol fee if one is charged.
+        Self::record_outflow(env, &token, amount);
+        let token_client = token::Client::new(env, &token);
+        if let Some(relayer) = &ext_data.relayer {
+            if ext_data.fee > 0 {
+                token_client.transfer(&env.current_contract_address(), relayer, &ext_data.fee);
+            }
+        }
+        if let Some(treasury) = treasury {
+            token_client.transfer(&env.current_contract_address(), &treasury, &protocol_fee);
+            let mut totals = Self::totals(env.clone(), token.clone());
+            totals.fees += protocol_fee;
+            Self::store_totals(env, &token, &totals);
+            env.events().publish(
+                (Symbol::new(env, "ProtocolFeeCharged"),),
+                (token.clone(), protocol_fee, treasury),
+            );
+        }
+        let payout = amount - ext_data.fee - protocol_fee;
+        if payout > 0 {
+            token_client.transfer(
+                &env.current_contract_address(),
+                &ext_data.recipient,
+                &payout,
+            );
+        }
+
+        env.events().publish(
+            (Symbol::new(env, "Withdrawn"),),
+            (
+                root,
+                ext_data.recipient,
+                nullifier_hash,
+                amount,
+                token,
+                ext_data.relayer,
+                ext_data.fee,
+                WITHDRAW_STATEMENT_VERSION,
+            ),
+        );
+
+        Ok(change_index)
     }
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
+    /// Validate a deposit, insert its leaf and store its record. The caller
+    /// authorizes the depositor and pulls the funds.
+    fn record_deposit(env: &Env, depositor: &Address, note: DepositNote) -> Result<u32, PoolError> {
+        let DepositNote { token, amount, commitment, system, refund_after, memo } = note;
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        if !merkle::is_field_element(&commitment) {
+            return Err(PoolError::InvalidCommitment);
+        }
+        if memo.as_ref().is_some_and(|m| m.len() > MAX_MEMO_LEN) {
+            return Err(PoolError::MemoTooLong);
+        }
+        let denomination = Self::set_denomination(env, amount);
+        if denomination != 0 && !Self::denominations(env.clone(), token.clone()).contains(amount) {
+            return Err(PoolError::InvalidDenomination);
+        }
+        if Self::is_paused(env, PauseScope::Deposits) {
+            return Err(PoolError::DepositsPaused);
+        }
+        if !env.storage().instance().has(&DataKey::Verifier(system)) {
+            return Err(PoolError::VerifierNotConfigured);
+        }
+        let config = Self::token_config(env.clone(), token.clone()).ok_or(PoolError::TokenNotAllowed)?;
+        if amount > config.max_deposit {
+            return Err(PoolError::DepositTooLarge);
+        }
+        let escrow = Self::escrow(env.clone(), token.clone());
+        if amount > config.escrow_cap - escrow {
+            return Err(PoolError::EscrowCapExceeded);
+        }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::testutils::{Address as _, Ledger};
-    use soroban_sdk::token::StellarAssetClient;
-    use soroban_sdk::Env;
+        if env.storage().persistent().has(&DataKey::Deposit(commitment.clone())) {
+            return Err(PoolError::CommitmentAlreadyDeposited);
+        }
 
-    // -----------------------------------------------------------------------
-    // Helpers
-    // -----------------------------------------------------------------------
+        // The leaf binds the amount received here, whatever C claims.
+        let leaf = note::leaf(env, &commitment, &Self::i128_to_bytes32(env, amount));
+        let (leaf_index, root) =
+            Self::insert_leaf(env, &(token.clone(), denomination, system), &leaf)?;
+        Self::record_inflow(env, &token, amount);
+
+        let record = DepositRecord {
+            commitment: commitment.clone(),
+            token: token.clone(),
+            amount,
+            depositor: depositor.clone(),
+            deposited_at: env.ledger().timestamp(),
+            leaf_index,
+            system,
+            refund_after,
+        };
+
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .persistent()
+            .set(&DataKey::Deposit(commitment.clone()), &record);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Deposit(commitment.clone()),
+            max_ttl,
+            max_ttl,
+        );
+
+        env.events().publish(
+            (Symbol::new(env, "Deposited"),),
+            (commitment, amount, token, depositor.clone(), leaf_index, root, refund_after, memo, system, leaf),
+        );
+
+        Ok(leaf_index)
+    }
+
+    /// `root` must be in the root history of commitment set `set` and at
+    /// least the minimum note age old.
+    fn require_known_root(
+        env: &Env,
+        set: &(Address, i128, ProofSystem),
+        root: &BytesN<32>,
+    ) -> Result<(), PoolError> {
+        let tree: merkle::MerkleTree = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Tree(set.clone()))
+            .ok_or(PoolError::UnknownRoot)?;
+        let root_time = tree.root_time(root).ok_or(PoolError::UnknownRoot)?;
+        if env.ledger().timestamp() < root_time + Self::min_note_age(env.clone()) {
+            return Err(PoolError::NoteTooYoung);
+        }
+        Ok(())
+    }
+
+    fn require_unspent(env: &Env, nullifier_hash: &BytesN<32>) -> Result<(), PoolError> {
+        if Self::is_nullifier_used(env.clone(), nullifier_hash.clone()) {
+            return Err(PoolError::NullifierAlreadySpent);
+        }
+        Ok(())
+    }
+
+    fn mark_spent(env: &Env, nullifier_hash: &BytesN<32>) {
+        let key = DataKey::NullifierUsed(nullifier_hash.clone());
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().set(&key, &true);
+        env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+
+    fn check_batch_size(len: u32) -> Result<(), PoolError> {
+        if len == 0 || len > MAX_BATCH_SIZE {
+            return Err(PoolError::InvalidBatchSize);
+        }
+        Ok(())
+    }
+
+    /// Append `leaf` to the tree of commitment set `set` and persist it.
+    /// Returns the leaf index and the new root.
+    fn insert_leaf(
+        env: &Env,
+        set: &(Address, i128, ProofSystem),
+        leaf: &BytesN<32>,
+    ) -> Result<(u32, BytesN<32>), PoolError> {
+        let mut tree = Self::load_tree(env, set);
+        let leaf_index = tree
+            .insert(env, leaf)
+            .ok_or(PoolError::MerkleTreeFull)?;
+        tree.roll_checkpoint(env, Self::min_note_age(env.clone()));
+        let key = DataKey::Tree(set.clone());
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().set(&key, &tree);
+        env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+        Ok((leaf_index, tree.root()))
+    }
+
+    fn load_tree(env: &Env, set: &(Address, i128, ProofSystem)) -> merkle::MerkleTree {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Tree(set.clone()))
+            .unwrap_or_else(|| merkle::MerkleTree::new(env))
+    }
+
+    fn mode(env: &Env) -> PoolMode {
+        env.storage()
+            .instance()
+            .get(&DataKey::Mode)
+            .unwrap_or(PoolMode::Open)
+    }
+
+    /// Denomination component of the commitment set a note of `amount`
+    /// belongs to: 0 in `Open` mode, the amount itself otherwise.
+    fn set_denomination(env: &Env, amount: i128) -> i128 {
+        match Self::mode(env) {
+            PoolMode::Open => 0,
+            PoolMode::FixedDenomination => amount,
+        }
+    }
+
+    /// sha256 over the XDR encoding, top byte cleared to fit the BN254 field.
+    fn hash_ext_data(env: &Env, ext_data: &ExtData) -> BytesN<32> {
+        Self::field_hash(env, &ext_data.clone().to_xdr(env))
+    }
+
+    fn hash_transfer_outputs(env: &Env, outputs: &Vec<TransferOutput>) -> BytesN<32> {
+        Self::field_hash(env, &outputs.clone().to_xdr(env))
+    }
+
+    /// sha256 with the top byte cleared, so the digest is a BN254 scalar.
+    fn field_hash(env: &Env, data: &Bytes) -> BytesN<32> {
+        let digest = env.crypto().sha256(data);
+        let mut arr = digest.to_array();
+        arr[0] = 0;
+        BytesN::from_array(env, &arr)
+    }
+
+    fn require_admin(env: &Env) -> Result<Address, PoolError> {
+        if Self::is_immutable(env.clone()) {
+            return Err(PoolError::AdminRenounced);
+        }
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(PoolError::NotAdmin)
+    }
+
+    fn record_inflow(env: &Env, token: &Address, amount: i128) {
+        let mut totals = Self::totals(env.clone(), token.clone());
+        totals.deposited += amount;
+        totals.outstanding += amount;
+        Self::store_totals(env, token, &totals);
+    }
+
+    fn record_outflow(env: &Env, token: &Address, amount: i128) {
+        let mut totals = Self::totals(env.clone(), token.clone());
+        totals.withdrawn += amount;
+        totals.outstanding -= amount;
+        Self::store_totals(env, token, &totals);
+    }
+
+    fn store_totals(env: &Env, token: &Address, totals: &TokenTotals) {
+        let key = DataKey::Totals(token.clone());
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().set(&key, totals);
+        env.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+
+    /// Protocol fee on a withdrawal of `amount`, rounded down.
+    fn protocol_fee_for(env: &Env, token: &Address, amount: i128) -> i128 {
+        let fee_bps = Self::protocol_fee(env.clone(), token.clone()) as i128;
+        // Split so that large amounts cannot overflow.
+        amount / 10_000 * fee_bps + amount % 10_000 * fee_bps / 10_000
+    }
+
+    fn require_guardian(env: &Env) -> Result<Address, PoolError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Guardian)
+            .ok_or(PoolError::NotGuardian)
+    }
+
+    fn paused_until(env: &Env, scope: PauseScope) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::PausedUntil(scope))
+            .unwrap_or(0)
+    }
+
+    fn is_paused(env: &Env, scope: PauseScope) -> bool {
+        env.ledger().timestamp() < Self::paused_until(env, scope)
+    }
+
+    /// When depositors can next withdraw: now, or the end of a running
+    /// withdrawal pause.
+    fn exit_window_start(env: &Env) -> u64 {
+        env.ledger()
+            .timestamp()
+            .max(Self::paused_until(env, PauseScope::Withdrawals))
+    }
+
+    /// Encode an i128 as a 32-byte big-endian value (high 16 bytes = 0).
+    pub fn i128_to_bytes32(env: &Env, value: i128) -> BytesN<32> {
+        let mut arr = [0u8; 32];
+        let be = value.to_be_bytes();
+        arr[16..32].copy_from_slice(&be);
+        BytesN::from_array(env, &arr)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Events, Ledger};
+    use soroban_sdk::{xdr, IntoVal, TryFromVal, Val};
+    use soroban_sdk::token::StellarAssetClient;
+    use soroban_sdk::Env;
+
+    // -----------------------------------------------------------------------
+    // Helpers
+    // -----------------------------------------------------------------------
+
+    fn create_token(env: &Env, admin: &Address) -> Address {
+        let sac = env.register_stellar_asset_contract_v2(admin.clone());
+        sac.address()
+    }
+
+    fn mint(env: &Env, token: &Address, to: &Address, amount: i128) {
+        // Get admin from SAC — works because we own it
+        let admin = Address::generate(env);
+        // We need to use the StellarAssetClient; since admin changes each call we
+        // just use mock_all_auths for convenience in tests.
+        StellarAssetClient::new(env, token).mint(to, &amount);
+    }
+
+    /// Mock verifier: always returns Ok(true).
+    #[contract]
+    struct MockVerifier;
+
+    #[contractimpl]
+    impl MockVerifier {
+        pub fn verify_groth16(
+            _env: Env,
+            _public_inputs: Vec<BytesN<32>>,
+            _proof: verifier::Groth16Proof,
+        ) -> bool {
+            true
+        }
+    }
+
+    /// Mock verifier: always panics (simulates invalid proof).
+    #[contract]
+    struct RejectVerifier;
+
+    #[contractimpl]
+    impl RejectVerifier {
+        pub fn verify_groth16(
+            _env: Env,
+            _public_inputs: Vec<BytesN<32>>,
+            _proof: verifier::Groth16Proof,
+        ) -> bool {
+            panic!("proof rejected")
+        }
+    }
+
+    /// Mock verifier: accepts everything and records the public inputs of
+    /// the last call so tests can assert the statement layout.
+    #[contract]
+    struct RecordingVerifier;
+
+    #[contractimpl]
+    impl RecordingVerifier {
+        pub fn verify_groth16(
+            env: Env,
+            public_inputs: Vec<BytesN<32>>,
+            _proof: verifier::Groth16Proof,
+        ) -> bool {
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "last"), &public_inputs);
+            true
+        }
+
+        pub fn last_inputs(env: Env) -> Vec<BytesN<32>> {
+            env.storage()
+                .instance()
+                .get(&Symbol::new(&env, "last"))
+                .unwrap()
+        }
+    }
+
+    /// Mock verifier: accepts only the exact statement it was pinned to,
+    /// standing in for a real proof generated over those public inputs.
+    #[contract]
+    struct PinnedVerifier;
+
+    #[contractimpl]
+    impl PinnedVerifier {
+        pub fn pin(env: Env, public_inputs: Vec<BytesN<32>>) {
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "pinned"), &public_inputs);
+        }
+
+        pub fn verify_groth16(
+            env: Env,
+            public_inputs: Vec<BytesN<32>>,
+            _proof: verifier::Groth16Proof,
+        ) -> bool {
+            let pinned: Vec<BytesN<32>> = env
+                .storage()
+                .instance()
+                .get(&Symbol::new(&env, "pinned"))
+                .unwrap();
+            if pinned != public_inputs {
+                panic!("proof does not match statement")
+            }
+            true
+        }
+    }
+
+    fn deploy_pool<'a>(env: &'a Env, admin: &Address, verifier_id: &Address) -> CommitmentPoolClient<'a> {
+        let id = env.register(CommitmentPool, ());
+        let client = CommitmentPoolClient::new(env, &id);
+        client.initialize(admin, verifier_id, &PoolMode::Open);
+        client
+    }
+
+    /// Appoint `guardian` through governance, advancing the ledger past the
+    /// delay.
+    fn appoint_guardian(env: &Env, pool: &CommitmentPoolClient, guardian: &Address) {
+        let proposal = pool.propose(&GovernanceAction::SetGuardian(guardian.clone()));
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at);
+        pool.execute_proposal();
+    }
+
+    /// Register `token` with no per-deposit or escrow limit.
+    fn list_token(pool: &CommitmentPoolClient, token: &Address) {
+        pool.set_token_config(
+            token,
+            &TokenConfig {
+                max_deposit: i128::MAX,
+                escrow_cap: i128::MAX,
+            },
+        );
+    }
+
+    fn sample_commitment(env: &Env, seed: u8) -> BytesN<32> {
+        let mut arr = [0u8; 32];
+        arr[31] = seed;
+        BytesN::from_array(env, &arr)
+    }
+
+    /// Payout straight to `recipient`, no relayer.
+    fn direct(recipient: &Address) -> ExtData {
+        ExtData {
+            recipient: recipient.clone(),
+            relayer: None,
+            fee: 0,
+        }
+    }
+
+    /// Public inputs the pool hands to the verifier for a withdrawal.
+    fn withdraw_statement(
+        env: &Env,
+        pool: &CommitmentPoolClient,
+        amount: i128,
+        root: &BytesN<32>,
+        nullifier_hash: &BytesN<32>,
+        change_leaf: Option<BytesN<32>>,
+        ext_data: &ExtData,
+    ) -> Vec<BytesN<32>> {
+        let mut statement = Vec::new(env);
+        statement.push_back(CommitmentPool::i128_to_bytes32(env, amount));
+        statement.push_back(root.clone());
+        statement.push_back(nullifier_hash.clone());
+        statement.push_back(change_leaf.unwrap_or_else(|| BytesN::from_array(env, &[0u8; 32])));
+        statement.push_back(pool.ext_data_hash(ext_data));
+        statement.push_back(pool.domain());
+        statement
+    }
+
+    fn sample_groth16(env: &Env) -> Groth16Proof {
+        Groth16Proof {
+            pi_a: BytesN::from_array(env, &[1u8; 64]),
+            pi_b: BytesN::from_array(env, &[2u8; 128]),
+            pi_c: BytesN::from_array(env, &[3u8; 64]),
+        }
+    }
+
+    fn sample_proof(env: &Env) -> WithdrawProof {
+        WithdrawProof::Groth16(sample_groth16(env))
+    }
+
+    // -----------------------------------------------------------------------
+    // Tests
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_initialize() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        assert_eq!(pool.admin(), admin);
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_id)));
+    }
+
+    #[test]
+    fn test_initialize_twice_fails() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        let res = pool.try_initialize(&admin, &verifier_id, &PoolMode::Open);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_deposit_records_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let commitment = sample_commitment(&env, 0x01);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+
+        let rec = pool.get_deposit(&commitment).expect("deposit should exist");
+        assert_eq!(rec.amount, 100_000_000);
+        assert_eq!(rec.token, token);
+        assert_eq!(rec.depositor, depositor);
+    }
+
+    #[test]
+    fn test_duplicate_commitment_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 2_000_000_000);
+
+        let commitment = sample_commitment(&env, 0x02);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_full_flow() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env); // completely different address!
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let commitment = sample_commitment(&env, 0x03);
+        let nullifier_hash = sample_commitment(&env, 0xAA);
+        let amount: i128 = 250_000_000;
+
+        pool.deposit(&depositor, &token, &amount, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        // The KEY insight: recipient is different from depositor — unlinked!
+        let proof = sample_proof(&env);
+        pool.withdraw(&token, &amount, &root, &proof, &nullifier_hash, &direct(&recipient));
+
+        let token_client = token::Client::new(&env, &token);
+        assert_eq!(token_client.balance(&recipient), amount);
+        // depositor did not receive anything
+        assert_eq!(token_client.balance(&depositor), 1_000_000_000 - amount);
+    }
+
+    #[test]
+    fn test_double_spend_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let commitment = sample_commitment(&env, 0x04);
+        let nullifier_hash = sample_commitment(&env, 0xBB);
+        let proof = sample_proof(&env);
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        pool.withdraw(&token, &100_000_000_i128, &root, &proof, &nullifier_hash, &direct(&recipient));
+
+        assert!(pool.is_nullifier_used(&nullifier_hash));
+
+        // Same nullifier — must fail
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &proof, &nullifier_hash, &direct(&recipient));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_invalid_proof_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(RejectVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let commitment = sample_commitment(&env, 0x05);
+        let nullifier_hash = sample_commitment(&env, 0xCC);
+        let proof = sample_proof(&env);
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &proof, &nullifier_hash, &direct(&recipient));
+        assert!(res.is_err());
+
+        // Nullifier must NOT be marked used on failed withdrawal
+        assert!(!pool.is_nullifier_used(&nullifier_hash));
+    }
+
+    #[test]
+    fn test_withdraw_unknown_root_fails() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x06), &ProofSystem::Groth16, &None, &None);
+
+        // A leaf value is not a root
+        let bogus_root = sample_commitment(&env, 0x06);
+        let nullifier_hash = sample_commitment(&env, 0xDD);
+        let proof = sample_proof(&env);
+
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &bogus_root, &proof, &nullifier_hash, &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::UnknownRoot)));
+    }
+
+    /// Roots are tracked per token: a root of one token's tree cannot be
+    /// used to withdraw another token.
+    #[test]
+    fn test_root_of_other_token_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token_a = create_token(&env, &admin);
+        let token_b = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token_a);
+        list_token(&pool, &token_b);
+        mint(&env, &token_a, &depositor, 1_000_000_000);
+        mint(&env, &token_b, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token_a, &100_000_000_i128, &sample_commitment(&env, 0x10), &ProofSystem::Groth16, &None, &None);
+        pool.deposit(&depositor, &token_b, &100_000_000_i128, &sample_commitment(&env, 0x11), &ProofSystem::Groth16, &None, &None);
+
+        let root_a = pool.get_root(&token_a, &0, &ProofSystem::Groth16);
+        let res = pool.try_withdraw(
+            &token_b,
+            &100_000_000_i128,
+            &root_a,
+            &sample_proof(&env),
+            &sample_commitment(&env, 0xDE),
+            &direct(&recipient),
+        );
+        assert_eq!(res, Err(Ok(PoolError::UnknownRoot)));
+    }
+
+    #[test]
+    fn test_zero_amount_deposit_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        list_token(&pool, &token);
+
+        let commitment = sample_commitment(&env, 0x07);
+        let res = pool.try_deposit(&depositor, &token, &0_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        assert!(res.is_err());
+    }
+
+    /// Commitments must be canonical BN254 scalars or they could not be
+    /// hashed into the tree (nor opened inside the circuit).
+    #[test]
+    fn test_non_field_commitment_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let commitment = BytesN::from_array(&env, &[0xFF; 32]);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::InvalidCommitment)));
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Merkle tree / anonymity set
+    // -----------------------------------------------------------------------
+
+    /// Each deposit becomes the next leaf; the root moves forward and the
+    /// previous root stays acceptable for in-flight proofs.
+    #[test]
+    fn test_deposits_append_leaves_and_roll_root() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let empty_root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        assert!(pool.is_known_root(&token, &0, &ProofSystem::Groth16, &empty_root));
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 0);
+
+        let c0 = sample_commitment(&env, 0x20);
+        let c1 = sample_commitment(&env, 0x21);
+        assert_eq!(pool.deposit(&depositor, &token, &100_000_000_i128, &c0, &ProofSystem::Groth16, &None, &None), 0);
+        let root_after_first = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        assert_eq!(pool.deposit(&depositor, &token, &100_000_000_i128, &c1, &ProofSystem::Groth16, &None, &None), 1);
+        let root_after_second = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        assert_ne!(empty_root, root_after_first);
+        assert_ne!(root_after_first, root_after_second);
+        assert!(pool.is_known_root(&token, &0, &ProofSystem::Groth16, &root_after_first));
+        assert!(pool.is_known_root(&token, &0, &ProofSystem::Groth16, &root_after_second));
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 2);
+        assert_eq!(pool.get_deposit(&c1).unwrap().leaf_index, 1);
+    }
+
+    /// Only the last ROOT_HISTORY_SIZE roots are accepted.
+    #[test]
+    fn test_root_history_expires_old_roots() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        pool.deposit(&depositor, &token, &1_000_i128, &sample_commitment(&env, 0x01), &ProofSystem::Groth16, &None, &None);
+        let old_root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        for i in 0..ROOT_HISTORY_SIZE - 1 {
+            pool.deposit(&depositor, &token, &1_000_i128, &sample_commitment(&env, 0x40 + i as u8), &ProofSystem::Groth16, &None, &None);
+        }
+        assert!(pool.is_known_root(&token, &0, &ProofSystem::Groth16, &old_root));
+
+        pool.deposit(&depositor, &token, &1_000_i128, &sample_commitment(&env, 0x80), &ProofSystem::Groth16, &None, &None);
+        assert!(!pool.is_known_root(&token, &0, &ProofSystem::Groth16, &old_root));
+    }
+
+    /// The withdraw statement is [amount, root, nullifier_hash,
+    /// change_leaf, ext_data_hash, domain]: the leaf being spent is never
+    /// handed to the verifier, and the nullifier hash and payout are bound
+    /// by the proof rather than trusted.
+    #[test]
+    fn test_withdraw_public_inputs_layout() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(RecordingVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let amount: i128 = 100_000_000;
+        pool.deposit(&depositor, &token, &amount, &sample_commitment(&env, 0x30), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        let nullifier_hash = sample_commitment(&env, 0xA0);
+        pool.withdraw(&token, &amount, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+
+        let inputs = RecordingVerifierClient::new(&env, &verifier_id).last_inputs();
+        assert_eq!(inputs.len(), 6);
+        assert_eq!(inputs.get(0).unwrap(), CommitmentPool::i128_to_bytes32(&env, amount));
+        assert_eq!(inputs.get(1).unwrap(), root);
+        assert_eq!(inputs.get(2).unwrap(), nullifier_hash);
+        // Full withdrawal: no change note
+        assert_eq!(inputs.get(3).unwrap(), BytesN::from_array(&env, &[0u8; 32]));
+        assert_eq!(inputs.get(4).unwrap(), pool.ext_data_hash(&direct(&recipient)));
+        // Always a canonical field element
+        assert_eq!(inputs.get(4).unwrap().to_array()[0], 0);
+        assert_eq!(inputs.get(5).unwrap(), pool.domain());
+    }
+
+    /// A proof made for one pool does not verify on another deployment that
+    /// shares the verifier and holds the same commitment.
+    #[test]
+    fn test_proof_bound_to_pool_deployment() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(PinnedVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool_a = deploy_pool(&env, &admin, &verifier_id);
+        let pool_b = deploy_pool(&env, &admin, &verifier_id);
+        assert_ne!(pool_a.domain(), pool_b.domain());
+
+        let amount: i128 = 100_000_000;
+        let commitment = sample_commitment(&env, 0x35);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        for pool in [&pool_a, &pool_b] {
+            list_token(pool, &token);
+            pool.deposit(&depositor, &token, &amount, &commitment, &ProofSystem::Groth16, &None, &None);
+        }
+        let root = pool_a.get_root(&token, &0, &ProofSystem::Groth16);
+        assert_eq!(root, pool_b.get_root(&token, &0, &ProofSystem::Groth16));
+
+        let nullifier_hash = sample_commitment(&env, 0xA5);
+        let statement = withdraw_statement(&env, &pool_a, amount, &root, &nullifier_hash, None, &direct(&recipient));
+        PinnedVerifierClient::new(&env, &verifier_id).pin(&statement);
+
+        let res = pool_b.try_withdraw(&token, &amount, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::InvalidProof)));
+        pool_a.withdraw(&token, &amount, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+    }
+
+    /// A proof generated for one recipient cannot be resubmitted by a
+    /// front-runner with their own address: the ext_data_hash no longer
+    /// matches what the proof committed to.
+    #[test]
+    fn test_front_run_with_other_recipient_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let attacker = Address::generate(&env);
+        let verifier_id = env.register(PinnedVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let amount: i128 = 100_000_000;
+        pool.deposit(&depositor, &token, &amount, &sample_commitment(&env, 0x32), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        let nullifier_hash = sample_commitment(&env, 0xA1);
+
+        // The "proof" is only valid for the statement it was generated for.
+        let statement = withdraw_statement(&env, &pool, amount, &root, &nullifier_hash, None, &direct(&recipient));
+        PinnedVerifierClient::new(&env, &verifier_id).pin(&statement);
+
+        let proof = sample_proof(&env);
+        let res = pool.try_withdraw(&token, &amount, &root, &proof, &nullifier_hash, &direct(&attacker));
+        assert!(res.is_err());
+        assert!(!pool.is_nullifier_used(&nullifier_hash));
+
+        pool.withdraw(&token, &amount, &root, &proof, &nullifier_hash, &direct(&recipient));
+        assert_eq!(token::Client::new(&env, &token).balance(&recipient), amount);
+        assert_eq!(token::Client::new(&env, &token).balance(&attacker), 0);
+    }
+
+    /// Relayed withdrawal: the relayer is paid the bound fee out of the
+    /// note and the recipient receives the rest.
+    #[test]
+    fn test_relayed_withdraw_splits_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let relayer = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let amount: i128 = 100_000_000;
+        pool.deposit(&depositor, &token, &amount, &sample_commitment(&env, 0x33), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        let ext_data = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: 1_500_000,
+        };
+        pool.withdraw(&token, &amount, &root, &sample_proof(&env), &sample_commitment(&env, 0xA2), &ext_data);
+
+        let token_client = token::Client::new(&env, &token);
+        assert_eq!(token_client.balance(&relayer), 1_500_000);
+        assert_eq!(token_client.balance(&recipient), amount - 1_500_000);
+        assert_eq!(token_client.balance(&pool.address), 0);
+    }
+
+    /// A relayer that raises its fee after receiving the proof invalidates it.
+    #[test]
+    fn test_relayer_cannot_change_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let relayer = Address::generate(&env);
+        let verifier_id = env.register(PinnedVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let amount: i128 = 100_000_000;
+        pool.deposit(&depositor, &token, &amount, &sample_commitment(&env, 0x34), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        let nullifier_hash = sample_commitment(&env, 0xA3);
+
+        let signed = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: 1_000_000,
+        };
+        let statement = withdraw_statement(&env, &pool, amount, &root, &nullifier_hash, None, &signed);
+        PinnedVerifierClient::new(&env, &verifier_id).pin(&statement);
+
+        let greedy = ExtData {
+            fee: 50_000_000,
+            ..signed.clone()
+        };
+        let proof = sample_proof(&env);
+        let res = pool.try_withdraw(&token, &amount, &root, &proof, &nullifier_hash, &greedy);
+        assert!(res.is_err());
+
+        pool.withdraw(&token, &amount, &root, &proof, &nullifier_hash, &signed);
+        assert_eq!(token::Client::new(&env, &token).balance(&relayer), 1_000_000);
+    }
+
+    // -----------------------------------------------------------------------
+    // Join-split
+    // -----------------------------------------------------------------------
+
+    /// Spend a note partially: the payout leaves the pool, the change note
+    /// is appended as a new leaf and can itself be spent afterwards.
+    #[test]
+    fn test_partial_withdraw_inserts_change_note() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(RecordingVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x50), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        let change = sample_commitment(&env, 0x51);
+        let nullifier_hash = sample_commitment(&env, 0xB0);
+        let leaf = pool.withdraw_with_change(
+            &token,
+            &30_000_000_i128,
+            &root,
+            &sample_proof(&env),
+            &nullifier_hash,
+            &change,
+            &direct(&recipient),
+        );
+        assert_eq!(leaf, 1);
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 2);
+        assert!(pool.is_nullifier_used(&nullifier_hash));
+
+        let inputs = RecordingVerifierClient::new(&env, &verifier_id).last_inputs();
+        assert_eq!(
+            inputs,
+            withdraw_statement(&env, &pool, 30_000_000, &root, &nullifier_hash, Some(change.clone()), &direct(&recipient))
+        );
+
+        let token_client = token::Client::new(&env, &token);
+        assert_eq!(token_client.balance(&recipient), 30_000_000);
+        assert_eq!(token_client.balance(&pool.address), 70_000_000);
+
+        // The change note is spendable against the new root.
+        let new_root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        assert_ne!(new_root, root);
+        pool.withdraw(
+            &token,
+            &70_000_000_i128,
+            &new_root,
+            &sample_proof(&env),
+            &sample_commitment(&env, 0xB1),
+            &direct(&recipient),
+        );
+        assert_eq!(token_client.balance(&recipient), 100_000_000);
+        assert_eq!(token_client.balance(&pool.address), 0);
+    }
+
+    /// 0 is the "no change" sentinel and cannot be inserted as a note.
+    #[test]
+    fn test_zero_change_commitment_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x52), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        let nullifier_hash = sample_commitment(&env, 0xB2);
+
+        let res = pool.try_withdraw_with_change(
+            &token,
+            &30_000_000_i128,
+            &root,
+            &sample_proof(&env),
+            &nullifier_hash,
+            &BytesN::from_array(&env, &[0u8; 32]),
+            &direct(&recipient),
+        );
+        assert_eq!(res, Err(Ok(PoolError::InvalidCommitment)));
+        assert!(!pool.is_nullifier_used(&nullifier_hash));
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 1);
+    }
+
+    #[test]
+    fn test_invalid_relayer_fee_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let relayer = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let amount: i128 = 100_000_000;
+        pool.deposit(&depositor, &token, &amount, &sample_commitment(&env, 0x35), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        let nullifier_hash = sample_commitment(&env, 0xA4);
+        let proof = sample_proof(&env);
+
+        // Fee larger than the note
+        let too_much = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: amount + 1,
+        };
+        let res = pool.try_withdraw(&token, &amount, &root, &proof, &nullifier_hash, &too_much);
+        assert_eq!(res, Err(Ok(PoolError::InvalidFee)));
+
+        // Negative fee
+        let negative = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: -1,
+        };
+        let res = pool.try_withdraw(&token, &amount, &root, &proof, &nullifier_hash, &negative);
+        assert_eq!(res, Err(Ok(PoolError::InvalidFee)));
+
+        // Fee with nobody to pay it to
+        let no_relayer = ExtData {
+            recipient: recipient.clone(),
+            relayer: None,
+            fee: 1,
+        };
+        let res = pool.try_withdraw(&token, &amount, &root, &proof, &nullifier_hash, &no_relayer);
+        assert_eq!(res, Err(Ok(PoolError::InvalidFee)));
+
+        assert!(!pool.is_nullifier_used(&nullifier_hash));
+    }
+
+    /// A nullifier hash >= the field modulus aliases a canonical one inside
+    /// the verifier, so it must be rejected before the spent-set lookup.
+    #[test]
+    fn test_non_canonical_nullifier_hash_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x31), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        let nullifier_hash = BytesN::from_array(&env, &[0xFF; 32]);
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::InvalidNullifierHash)));
+    }
+
+    #[test]
+    fn test_i128_to_bytes32_encoding() {
+        let env = Env::default();
+
+        // 1 stroop → last byte = 1, rest = 0
+        let b = CommitmentPool::i128_to_bytes32(&env, 1);
+        let arr = b.to_array();
+        assert_eq!(arr[31], 1u8);
+        for i in 0..31 {
+            assert_eq!(arr[i], 0u8);
+        }
+
+        // 256 = 0x100 → arr[30]=1, arr[31]=0
+        let b256 = CommitmentPool::i128_to_bytes32(&env, 256);
+        let arr256 = b256.to_array();
+        assert_eq!(arr256[30], 1u8);
+        assert_eq!(arr256[31], 0u8);
+    }
+
+    // -----------------------------------------------------------------------
+    // Admin / governance tests (Gate 5 — Auth)
+    // -----------------------------------------------------------------------
+
+    /// Propose `action` and execute it once the governance delay elapses.
+    fn govern(env: &Env, pool: &CommitmentPoolClient, action: GovernanceAction) {
+        let proposal = pool.propose(&action);
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at);
+        pool.execute_proposal();
+    }
+
+    /// Admin can replace the verifier address. The new verifier is used for
+    /// all subsequent withdrawals. This is a critical governance operation:
+    /// once replaced, all pending withdrawal proofs must be valid under the
+    /// new verifier's key. Audit note: verifier replacement is admin-only
+    /// and must be disclosed to users — see docs/audits/discombobulator-pr117/THREAT_MODEL.md.
+    #[test]
+    fn test_admin_can_replace_verifier() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let verifier_a = env.register(MockVerifier, ());
+        let verifier_b = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_a);
+
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_a)));
+        govern(&env, &pool, GovernanceAction::SetVerifier(VerifierBackend::Groth16(verifier_b.clone())));
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_b)));
+        assert_eq!(pool.pending_proposal(), None);
+    }
+
+    /// A proposal cannot execute before the delay, and while it waits the
+    /// old verifier stays in force.
+    #[test]
+    fn test_proposal_respects_delay() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let verifier_a = env.register(MockVerifier, ());
+        let verifier_b = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_a);
+
+        let action = GovernanceAction::SetVerifier(VerifierBackend::Groth16(verifier_b.clone()));
+        let proposal = pool.propose(&action);
+        assert_eq!(proposal.executable_at, 1_000_000 + GOVERNANCE_DELAY);
+        assert_eq!(pool.pending_proposal(), Some(proposal.clone()));
+
+        // Only one proposal at a time
+        let res = pool.try_propose(&GovernanceAction::Upgrade(BytesN::from_array(&env, &[1u8; 32])));
+        assert_eq!(res, Err(Ok(PoolError::ProposalPending)));
+
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at - 1);
+        assert_eq!(pool.try_execute_proposal(), Err(Ok(PoolError::ProposalNotReady)));
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_a)));
+
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at);
+        pool.execute_proposal();
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_b)));
+        assert_eq!(pool.try_execute_proposal(), Err(Ok(PoolError::NoPendingProposal)));
+    }
+
+    #[test]
+    fn test_cancelled_proposal_never_executes() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let verifier_a = env.register(MockVerifier, ());
+        let verifier_b = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_a);
+
+        let proposal = pool.propose(&GovernanceAction::SetVerifier(VerifierBackend::Groth16(verifier_b)));
+        pool.cancel_proposal();
+        assert_eq!(pool.pending_proposal(), None);
+
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at);
+        assert_eq!(pool.try_execute_proposal(), Err(Ok(PoolError::NoPendingProposal)));
+        assert_eq!(pool.try_cancel_proposal(), Err(Ok(PoolError::NoPendingProposal)));
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_a)));
+    }
+
+    /// After the verifier is replaced with a reject-all verifier, all
+    /// withdrawal proofs must fail — even for deposits made before the switch.
+    /// This confirms that verifier replacement is an effective governance lever
+    /// over all outstanding escrowed funds. Users must trust the admin.
+    #[test]
+    fn test_verifier_replacement_gates_future_withdrawals() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_ok = env.register(MockVerifier, ());
+        let verifier_reject = env.register(RejectVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_ok);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let commitment = sample_commitment(&env, 0x08);
+        let nullifier_hash = sample_commitment(&env, 0xEE);
+        let proof = sample_proof(&env);
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        // Admin replaces verifier with one that always rejects
+        govern(&env, &pool, GovernanceAction::SetVerifier(VerifierBackend::Groth16(verifier_reject)));
+
+        // Withdrawal now fails — the escrowed deposit is frozen until
+        // a valid verifier is restored (or the admin acts)
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &proof, &nullifier_hash, &direct(&recipient));
+        assert!(res.is_err());
+
+        // Nullifier must NOT be marked used when proof fails post-verifier-swap
+        assert!(!pool.is_nullifier_used(&nullifier_hash));
+    }
+
+    /// A deposit's leaf binds the amount the pool received, not the amount
+    /// inside the commitment: a note claiming more than was paid in has no
+    /// leaf under any known root, so it cannot be withdrawn for that amount.
+    #[test]
+    fn test_deposit_leaf_binds_received_amount() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000);
+        // C is built for 100_000_000 but only 1_000 is paid in.
+        let commitment = sample_commitment(&env, 0x61);
+        pool.deposit(&depositor, &token, &1_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        let (paid_root, claimed_root) = env.as_contract(&pool.address, || {
+            let mut paid = merkle::MerkleTree::new(&env);
+            paid.insert(&env, &note::leaf(&env, &commitment, &CommitmentPool::i128_to_bytes32(&env, 1_000)));
+            let mut claimed = merkle::MerkleTree::new(&env);
+            claimed.insert(&env, &note::leaf(&env, &commitment, &CommitmentPool::i128_to_bytes32(&env, 100_000_000)));
+            (paid.root(), claimed.root())
+        });
+        assert_eq!(root, paid_root);
+        assert_ne!(root, claimed_root);
+
+        // The only tree the claimed amount opens in is not one the pool knows.
+        let res = pool.try_withdraw(
+            &token,
+            &100_000_000_i128,
+            &claimed_root,
+            &sample_proof(&env),
+            &sample_commitment(&env, 0x62),
+            &direct(&recipient),
+        );
+        assert_eq!(res, Err(Ok(PoolError::UnknownRoot)));
+    }
+
+    /// withdraw_statement_version() exposes the current statement schema version
+    /// so clients can detect version drift before submitting proofs.
+    #[test]
+    fn test_withdraw_statement_version_is_v6() {
+        let env = Env::default();
+        let pool_id = env.register(CommitmentPool, ());
+        let pool = CommitmentPoolClient::new(&env, &pool_id);
+        assert_eq!(pool.withdraw_statement_version(), 6u32);
+    }
+
+    /// Verifier is readable before and after replacement via the public
+    /// `verifier()` read-only function — callers can detect governance changes.
+    #[test]
+    fn test_verifier_address_is_readable() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let verifier_a = env.register(MockVerifier, ());
+        let verifier_b = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_a);
+
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_a)));
+        govern(&env, &pool, GovernanceAction::SetVerifier(VerifierBackend::Groth16(verifier_b.clone())));
+        assert_eq!(pool.verifier(&ProofSystem::Groth16), Some(VerifierBackend::Groth16(verifier_b)));
+        // admin unchanged
+        assert_eq!(pool.admin(), admin);
+    }
+
+    fn deploy_fixed_pool<'a>(env: &'a Env, admin: &Address, verifier_id: &Address) -> CommitmentPoolClient<'a> {
+        let id = env.register(CommitmentPool, ());
+        let client = CommitmentPoolClient::new(env, &id);
+        client.initialize(admin, verifier_id, &PoolMode::FixedDenomination);
+        client
+    }
+
+    #[test]
+    fn test_fixed_mode_rejects_unlisted_amount() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_fixed_pool(&env, &admin, &verifier_id);
+        assert_eq!(pool.pool_mode(), PoolMode::FixedDenomination);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.add_denomination(&token, &100_000_000_i128);
+        assert_eq!(pool.denominations(&token), Vec::from_array(&env, [100_000_000_i128]));
+
+        let res = pool.try_deposit(&depositor, &token, &50_000_000_i128, &sample_commitment(&env, 0x60), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::InvalidDenomination)));
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x61), &ProofSystem::Groth16, &None, &None);
+
+        // Removed denominations stop accepting deposits.
+        pool.remove_denomination(&token, &100_000_000_i128);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x62), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::InvalidDenomination)));
+    }
+
+    #[test]
+    fn test_denominations_only_in_fixed_mode() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        let res = pool.try_add_denomination(&token, &100_000_000_i128);
+        assert_eq!(res, Err(Ok(PoolError::UnsupportedInPoolMode)));
+    }
+
+    #[test]
+    fn test_fixed_mode_separates_sets_per_denomination() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_fixed_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.add_denomination(&token, &10_000_000_i128);
+        pool.add_denomination(&token, &100_000_000_i128);
+
+        assert_eq!(pool.deposit(&depositor, &token, &10_000_000_i128, &sample_commitment(&env, 0x63), &ProofSystem::Groth16, &None, &None), 0);
+        assert_eq!(pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x64), &ProofSystem::Groth16, &None, &None), 0);
+        assert_eq!(pool.deposit(&depositor, &token, &10_000_000_i128, &sample_commitment(&env, 0x65), &ProofSystem::Groth16, &None, &None), 1);
+        assert_eq!(pool.commitment_count(&token, &10_000_000, &ProofSystem::Groth16), 2);
+        assert_eq!(pool.commitment_count(&token, &100_000_000, &ProofSystem::Groth16), 1);
+
+        let small_root = pool.get_root(&token, &10_000_000, &ProofSystem::Groth16);
+        let large_root = pool.get_root(&token, &100_000_000, &ProofSystem::Groth16);
+        assert_ne!(small_root, large_root);
+
+        // A root of the 10M set cannot back a 100M withdrawal.
+        let res = pool.try_withdraw(
+            &token,
+            &100_000_000_i128,
+            &small_root,
+            &sample_proof(&env),
+            &sample_commitment(&env, 0xC0),
+            &direct(&recipient),
+        );
+        assert_eq!(res, Err(Ok(PoolError::UnknownRoot)));
+
+        pool.withdraw(
+            &token,
+            &100_000_000_i128,
+            &large_root,
+            &sample_proof(&env),
+            &sample_commitment(&env, 0xC1),
+            &direct(&recipient),
+        );
+        assert_eq!(token::Client::new(&env, &token).balance(&recipient), 100_000_000);
+    }
+
+    #[test]
+    fn test_fixed_mode_rejects_join_split() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_fixed_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.add_denomination(&token, &100_000_000_i128);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x66), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &100_000_000, &ProofSystem::Groth16);
+
+        let res = pool.try_withdraw_with_change(
+            &token,
+            &30_000_000_i128,
+            &root,
+            &sample_proof(&env),
+            &sample_commitment(&env, 0xC2),
+            &sample_commitment(&env, 0x67),
+            &direct(&recipient),
+        );
+        assert_eq!(res, Err(Ok(PoolError::UnsupportedInPoolMode)));
+    }
+
+    /// Real note opening for the refund tests: C = Poseidon(secret, amount, nullifier).
+    fn refundable_note(env: &Env, amount: i128, seed: u8) -> (BytesN<32>, BytesN<32>, BytesN<32>) {
+        let secret = sample_commitment(env, seed);
+        let nullifier = sample_commitment(env, seed + 1);
+        let amount = CommitmentPool::i128_to_bytes32(env, amount);
+        let commitment = note::commitment(env, &secret, &amount, &nullifier);
+        (commitment, secret, nullifier)
+    }
+
+    #[test]
+    fn test_refund_after_timelock() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        let token_client = token::Client::new(&env, &token);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let amount: i128 = 100_000_000;
+        let (commitment, secret, nullifier) = refundable_note(&env, amount, 0x70);
+        let now = env.ledger().timestamp();
+        pool.deposit(&depositor, &token, &amount, &commitment, &ProofSystem::Groth16, &Some(now + 1_000), &None);
+        assert_eq!(pool.get_deposit(&commitment).unwrap().refund_after, Some(now + 1_000));
+
+        let res = pool.try_refund(&commitment, &secret, &nullifier);
+        assert_eq!(res, Err(Ok(PoolError::RefundLocked)));
+
+        env.ledger().with_mut(|l| l.timestamp = now + 1_000);
+
+        // Wrong opening
+        let res = pool.try_refund(&commitment, &nullifier, &secret);
+        assert_eq!(res, Err(Ok(PoolError::InvalidNoteOpening)));
+
+        pool.refund(&commitment, &secret, &nullifier);
+        assert_eq!(token_client.balance(&depositor), 1_000_000_000);
+        assert_eq!(token_client.balance(&pool.address), 0);
+
+        let nullifier_hash = note::nullifier_hash(&env, &nullifier);
+        assert!(pool.is_nullifier_used(&nullifier_hash));
+
+        // Neither a second refund nor a withdrawal can spend the note again.
+        let res = pool.try_refund(&commitment, &secret, &nullifier);
+        assert_eq!(res, Err(Ok(PoolError::NullifierAlreadySpent)));
+        let res = pool.try_withdraw(
+            &token,
+            &amount,
+            &pool.get_root(&token, &0, &ProofSystem::Groth16),
+            &sample_proof(&env),
+            &nullifier_hash,
+            &direct(&recipient),
+        );
+        assert_eq!(res, Err(Ok(PoolError::NullifierAlreadySpent)));
+    }
+
+    #[test]
+    fn test_refund_requires_opt_in() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let (commitment, secret, nullifier) = refundable_note(&env, 100_000_000, 0x72);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+
+        env.ledger().with_mut(|l| l.timestamp += 1_000_000);
+        let res = pool.try_refund(&commitment, &secret, &nullifier);
+        assert_eq!(res, Err(Ok(PoolError::RefundNotEnabled)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_refund_requires_depositor_auth() {
+        let env = Env::default();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        env.mock_all_auths();
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        let (commitment, secret, nullifier) = refundable_note(&env, 100_000_000, 0x74);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &Some(0), &None);
+
+        // Drop mocked auths: nobody signs for the depositor.
+        env.set_auths(&[]);
+        pool.refund(&commitment, &secret, &nullifier);
+    }
+
+    #[test]
+    fn test_guardian_pauses_deposits_and_withdrawals_independently() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let guardian = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x80), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        assert_eq!(pool.try_pause(&PauseScope::Deposits, &3_600), Err(Ok(PoolError::NotGuardian)));
+        appoint_guardian(&env, &pool, &guardian);
+        assert_eq!(pool.guardian(), Some(guardian));
+
+        let until = pool.pause(&PauseScope::Deposits, &3_600);
+        assert_eq!(
+            pool.pause_state(),
+            PauseState { deposits_paused_until: until, withdrawals_paused_until: 0 }
+        );
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x81), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::DepositsPaused)));
+
+        // Withdrawals are unaffected by a deposit pause...
+        pool.withdraw(&token, &50_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xD0), &direct(&recipient));
+
+        // ...and vice versa.
+        pool.unpause(&PauseScope::Deposits);
+        pool.pause(&PauseScope::Withdrawals, &3_600);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x81), &ProofSystem::Groth16, &None, &None);
+        let res = pool.try_withdraw(&token, &50_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xD1), &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::WithdrawalsPaused)));
+    }
+
+    #[test]
+    fn test_pause_expires_and_is_capped() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let guardian = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        appoint_guardian(&env, &pool, &guardian);
+
+        let res = pool.try_pause(&PauseScope::Deposits, &(MAX_PAUSE_DURATION + 1));
+        assert_eq!(res, Err(Ok(PoolError::InvalidPauseDuration)));
+
+        let until = pool.pause(&PauseScope::Deposits, &MAX_PAUSE_DURATION);
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        env.ledger().with_mut(|l| l.timestamp = until - 1);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x82), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::DepositsPaused)));
+
+        env.ledger().with_mut(|l| l.timestamp = until);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x82), &ProofSystem::Groth16, &None, &None);
+    }
+
+    #[test]
+    fn test_withdrawal_pause_cannot_shorten_governance_delay() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let guardian = Address::generate(&env);
+        let verifier_a = env.register(MockVerifier, ());
+        let verifier_b = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_a);
+        appoint_guardian(&env, &pool, &guardian);
+        assert_eq!(pool.guardian(), Some(guardian));
+
+        let action = GovernanceAction::SetVerifier(VerifierBackend::Groth16(verifier_b));
+        let proposal = pool.propose(&action);
+        let until = pool.pause(&PauseScope::Withdrawals, &MAX_PAUSE_DURATION);
+        assert_eq!(pool.pending_proposal().unwrap().executable_at, until + GOVERNANCE_DELAY);
+
+        // A running pause can neither be replaced nor re-armed right after
+        // it is lifted.
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at);
+        assert_eq!(pool.try_pause(&PauseScope::Withdrawals, &3_600), Err(Ok(PoolError::PauseCoolingDown)));
+        assert_eq!(pool.try_execute_proposal(), Err(Ok(PoolError::WithdrawalsPaused)));
+        pool.unpause(&PauseScope::Withdrawals);
+        assert_eq!(pool.try_execute_proposal(), Err(Ok(PoolError::ProposalNotReady)));
+        let lifted = env.ledger().timestamp();
+        env.ledger().with_mut(|l| l.timestamp = lifted + PAUSE_COOLDOWN - 1);
+        assert_eq!(pool.try_pause(&PauseScope::Withdrawals, &3_600), Err(Ok(PoolError::PauseCoolingDown)));
+
+        // Proposals made during a pause wait for a full delay after it.
+        pool.cancel_proposal();
+        env.ledger().with_mut(|l| l.timestamp = lifted + PAUSE_COOLDOWN);
+        let until = pool.pause(&PauseScope::Withdrawals, &3_600);
+        assert_eq!(pool.propose(&action).executable_at, until + GOVERNANCE_DELAY);
+        env.ledger().with_mut(|l| l.timestamp = until + GOVERNANCE_DELAY);
+        pool.execute_proposal();
+    }
+
+    #[test]
+    fn test_repeated_withdrawal_pause_cannot_defer_guardian_replacement() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let guardian = Address::generate(&env);
+        let successor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        appoint_guardian(&env, &pool, &guardian);
+
+        let mut until = pool.pause(&PauseScope::Withdrawals, &MAX_PAUSE_DURATION);
+        let proposal = pool.propose(&GovernanceAction::SetGuardian(successor.clone()));
+        assert_eq!(proposal.executable_at, proposal.proposed_at + GOVERNANCE_DELAY);
+
+        // The guardian re-pauses as soon as each cooldown allows; the
+        // replacement keeps its original time.
+        for _ in 0..3 {
+            env.ledger().with_mut(|l| l.timestamp = until + PAUSE_COOLDOWN);
+            until = pool.pause(&PauseScope::Withdrawals, &MAX_PAUSE_DURATION);
+            assert_eq!(pool.pending_proposal(), Some(proposal.clone()));
+        }
+
+        // It executes while withdrawals are still paused.
+        assert!(pool.pause_state().withdrawals_paused_until > env.ledger().timestamp());
+        pool.execute_proposal();
+        assert_eq!(pool.guardian(), Some(successor));
+    }
+
+    #[test]
+    fn test_unlisted_token_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        mint(&env, &token, &depositor, 1_000_000_000);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x90), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::TokenNotAllowed)));
+
+        list_token(&pool, &token);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x90), &ProofSystem::Groth16, &None, &None);
+
+        pool.remove_token(&token);
+        assert_eq!(pool.token_config(&token), None);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x91), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::TokenNotAllowed)));
+    }
+
+    #[test]
+    fn test_deposit_limits_and_escrow_tracking() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        pool.set_token_config(
+            &token,
+            &TokenConfig {
+                max_deposit: 100_000_000,
+                escrow_cap: 150_000_000,
+            },
+        );
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let res = pool.try_deposit(&depositor, &token, &100_000_001_i128, &sample_commitment(&env, 0x92), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::DepositTooLarge)));
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x92), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(pool.escrow(&token), 100_000_000);
+
+        let res = pool.try_deposit(&depositor, &token, &60_000_000_i128, &sample_commitment(&env, 0x93), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::EscrowCapExceeded)));
+        pool.deposit(&depositor, &token, &50_000_000_i128, &sample_commitment(&env, 0x93), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(pool.escrow(&token), 150_000_000);
+
+        // Withdrawals free up room under the cap.
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xE0), &direct(&recipient));
+        assert_eq!(pool.escrow(&token), 50_000_000);
+        pool.deposit(&depositor, &token, &60_000_000_i128, &sample_commitment(&env, 0x94), &ProofSystem::Groth16, &None, &None);
+        assert_eq!(pool.escrow(&token), 110_000_000);
+    }
+
+    #[test]
+    fn test_deposit_memo_is_emitted_and_bounded() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let too_long = Bytes::from_array(&env, &[7u8; MAX_MEMO_LEN as usize + 1]);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x95), &ProofSystem::Groth16, &None, &Some(too_long));
+        assert_eq!(res, Err(Ok(PoolError::MemoTooLong)));
+
+        let memo = Bytes::from_array(&env, &[7u8; 128]);
+        let commitment = sample_commitment(&env, 0x95);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &Some(memo.clone()));
+
+        let events = env.events().all().filter_by_contract(&pool.address);
+        let xdr::ContractEventBody::V0(body) = &events.events().last().unwrap().body;
+        let data = Val::try_from_val(&env, &body.data).unwrap();
+        #[allow(clippy::type_complexity)]
+        let (c, _, _, _, _, _, _, m, _, _): (
+            BytesN<32>,
+            i128,
+            Address,
+            Address,
+            u32,
+            BytesN<32>,
+            Option<u64>,
+            Option<Bytes>,
+            ProofSystem,
+            BytesN<32>,
+        ) = data.into_val(&env);
+        assert_eq!(c, commitment);
+        assert_eq!(m, Some(memo));
+    }
+
+    fn deposit_note(env: &Env, token: &Address, amount: i128, seed: u8) -> DepositNote {
+        DepositNote {
+            token: token.clone(),
+            amount,
+            commitment: sample_commitment(env, seed),
+            system: ProofSystem::Groth16,
+            refund_after: None,
+            memo: None,
+        }
+    }
+
+    #[test]
+    fn test_batch_deposit_pulls_once_per_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token_a = create_token(&env, &admin);
+        let token_b = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token_a);
+        list_token(&pool, &token_b);
+        mint(&env, &token_a, &depositor, 1_000_000_000);
+        mint(&env, &token_b, &depositor, 1_000_000_000);
+
+        let notes = Vec::from_array(
+            &env,
+            [
+                deposit_note(&env, &token_a, 10_000_000, 0xA0),
+                deposit_note(&env, &token_b, 20_000_000, 0xA1),
+                deposit_note(&env, &token_a, 30_000_000, 0xA2),
+            ],
+        );
+        let leaves = pool.batch_deposit(&depositor, &notes);
+        assert_eq!(leaves, Vec::from_array(&env, [0u32, 0, 1]));
+
+        // One transfer event per token for the whole batch
+        let events = env.events().all();
+        assert_eq!(events.filter_by_contract(&token_a).events().len(), 1);
+        assert_eq!(events.filter_by_contract(&token_b).events().len(), 1);
+
+        assert_eq!(token::Client::new(&env, &token_a).balance(&pool.address), 40_000_000);
+        assert_eq!(token::Client::new(&env, &token_b).balance(&pool.address), 20_000_000);
+        assert_eq!(pool.commitment_count(&token_a, &0, &ProofSystem::Groth16), 2);
+        assert_eq!(pool.escrow(&token_a), 40_000_000);
+        assert!(pool.get_deposit(&sample_commitment(&env, 0xA2)).is_some());
+    }
+
+    #[test]
+    fn test_batch_deposit_is_atomic_and_bounded() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        // Duplicate commitment in the second note rolls back the first.
+        let notes = Vec::from_array(
+            &env,
+            [
+                deposit_note(&env, &token, 10_000_000, 0xA3),
+                deposit_note(&env, &token, 10_000_000, 0xA3),
+            ],
+        );
+        let res = pool.try_batch_deposit(&depositor, &notes);
+        assert_eq!(res, Err(Ok(PoolError::CommitmentAlreadyDeposited)));
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 0);
+        assert!(pool.get_deposit(&sample_commitment(&env, 0xA3)).is_none());
+
+        let res = pool.try_batch_deposit(&depositor, &Vec::new(&env));
+        assert_eq!(res, Err(Ok(PoolError::InvalidBatchSize)));
+        let mut too_many = Vec::new(&env);
+        for i in 0..=MAX_BATCH_SIZE {
+            too_many.push_back(deposit_note(&env, &token, 1_000, 0xB0 + i as u8));
+        }
+        let res = pool.try_batch_deposit(&depositor, &too_many);
+        assert_eq!(res, Err(Ok(PoolError::InvalidBatchSize)));
+    }
+
+    #[test]
+    fn test_batch_withdraw_is_atomic() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        let token_client = token::Client::new(&env, &token);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &10_000_000_i128, &sample_commitment(&env, 0xA4), &ProofSystem::Groth16, &None, &None);
+        pool.deposit(&depositor, &token, &20_000_000_i128, &sample_commitment(&env, 0xA5), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        let spend = |amount: i128, seed: u8| Withdrawal {
+            token: token.clone(),
+            amount,
+            root: root.clone(),
+            proof: sample_proof(&env),
+            nullifier_hash: sample_commitment(&env, seed),
+            ext_data: direct(&recipient),
+        };
+
+        // Second spend reuses the first nullifier: nothing is paid out.
+        let res = pool.try_batch_withdraw(&Vec::from_array(&env, [spend(10_000_000, 0xF0), spend(20_000_000, 0xF0)]));
+        assert_eq!(res, Err(Ok(PoolError::NullifierAlreadySpent)));
+        assert!(!pool.is_nullifier_used(&sample_commitment(&env, 0xF0)));
+        assert_eq!(token_client.balance(&recipient), 0);
+
+        pool.batch_withdraw(&Vec::from_array(&env, [spend(10_000_000, 0xF0), spend(20_000_000, 0xF1)]));
+        assert_eq!(token_client.balance(&recipient), 30_000_000);
+        assert_eq!(pool.escrow(&token), 0);
+    }
+
+    #[test]
+    fn test_totals_and_solvency() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
+
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let relayer = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+
+        let (commitment, secret, nullifier) = refundable_note(&env, 50_000_000, 0xA6);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0xA8), &ProofSystem::Groth16, &None, &None);
+        pool.deposit(&depositor, &token, &50_000_000_i128, &commitment, &ProofSystem::Groth16, &Some(1_000_000), &None);
+
+        // Relayed withdrawal: the fee leaves the pool too.
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        let ext_data = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer),
+            fee: 1_000_000,
+        };
+        pool.withdraw(&token, &60_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF2), &ext_data);
+        pool.refund(&commitment, &secret, &nullifier);
 
-    fn create_token(env: &Env, admin: &Address) -> Address {
-        let sac = env.register_stellar_asset_contract_v2(admin.clone());
-        sac.address()
-    }
+        assert_eq!(
+            pool.totals(&token),
+            TokenTotals { deposited: 150_000_000, withdrawn: 110_000_000, outstanding: 40_000_000, fees: 0 }
+        );
+        assert_eq!(
+            pool.check_solvency(&token),
+            Solvency { outstanding: 40_000_000, balance: 40_000_000, solvent: true }
+        );
 
-    fn mint(env: &Env, token: &Address, to: &Address, amount: i128) {
-        // Get admin from SAC — works because we own it
-        let admin = Address::generate(env);
-        // We need to use the StellarAssetClient; since admin changes each call we
-        // just use mock_all_auths for convenience in tests.
-        StellarAssetClient::new(env, token).mint(to, &amount);
+        // Tokens sent to the pool outside deposit() are a surplus, not a liability.
+        mint(&env, &token, &pool.address, 5_000_000);
+        assert_eq!(
+            pool.check_solvency(&token),
+            Solvency { outstanding: 40_000_000, balance: 45_000_000, solvent: true }
+        );
     }
 
-    /// Mock verifier: always returns Ok(true).
+    /// Mock ultrahonk-verifier: accepts everything and records the packed
+    /// public inputs of the last call. Its VK hashes to `[7; 32]` until
+    /// `set_vk_hash` swaps it.
     #[contract]
-    struct MockVerifier;
+    struct MockUltraHonk;
 
     #[contractimpl]
-    impl MockVerifier {
-        pub fn verify_groth16(
-            _env: Env,
-            _public_inputs: Vec<BytesN<32>>,
-            _proof: verifier::Groth16Proof,
-        ) -> bool {
+    impl MockUltraHonk {
+        pub fn vk_hash_by_id(env: Env, _vk_id: Symbol) -> Option<BytesN<32>> {
+            Some(
+                env.storage()
+                    .instance()
+                    .get(&Symbol::new(&env, "vk"))
+                    .unwrap_or(BytesN::from_array(&env, &[7; 32])),
+            )
+        }
+
+        pub fn set_vk_hash(env: Env, vk_hash: BytesN<32>) {
+            env.storage().instance().set(&Symbol::new(&env, "vk"), &vk_hash);
+        }
+
+        pub fn verify_proof_vk(env: Env, _vk_id: Symbol, public_inputs: Bytes, _proof_bytes: Bytes) -> bool {
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "last"), &public_inputs);
             true
         }
+
+        pub fn last_inputs(env: Env) -> Bytes {
+            env.storage()
+                .instance()
+                .get(&Symbol::new(&env, "last"))
+                .unwrap()
+        }
     }
 
-    /// Mock verifier: always panics (simulates invalid proof).
+    /// Mock risc0-groth16-verifier: checks the owner's auth like the real
+    /// contract and, like its per-owner attestation, records the owner,
+    /// claim and receipt public inputs of the last call.
     #[contract]
-    struct RejectVerifier;
+    struct MockRisc0;
 
     #[contractimpl]
-    impl RejectVerifier {
-        pub fn verify_groth16(
-            _env: Env,
-            _public_inputs: Vec<BytesN<32>>,
-            _proof: verifier::Groth16Proof,
+    impl MockRisc0 {
+        pub fn verify_and_attest(
+            env: Env,
+            owner: Address,
+            claim_digest: BytesN<32>,
+            public_inputs: Vec<BytesN<32>>,
+            _proof: Groth16Proof,
         ) -> bool {
-            panic!("proof rejected")
+            owner.require_auth();
+            env.storage()
+                .instance()
+                .set(&Symbol::new(&env, "last"), &(owner, claim_digest, public_inputs));
+            true
         }
-    }
-
-    fn deploy_pool<'a>(env: &'a Env, admin: &Address, verifier_id: &Address) -> CommitmentPoolClient<'a> {
-        let id = env.register(CommitmentPool, ());
-        let client = CommitmentPoolClient::new(env, &id);
-        client.initialize(admin, verifier_id);
-        client
-    }
-
-    fn sample_commitment(env: &Env, seed: u8) -> BytesN<32> {
-        let mut arr = [0u8; 32];
-        arr[31] = seed;
-        BytesN::from_array(env, &arr)
-    }
 
-    fn sample_proof(env: &Env) -> Groth16Proof {
-        Groth16Proof {
-            pi_a: BytesN::from_array(env, &[1u8; 64]),
-            pi_b: BytesN::from_array(env, &[2u8; 128]),
-            pi_c: BytesN::from_array(env, &[3u8; 64]),
+        pub fn last_inputs(env: Env) -> (Address, BytesN<32>, Vec<BytesN<32>>) {
+            env.storage()
+                .instance()
+                .get(&Symbol::new(&env, "last"))
+                .unwrap()
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Tests
-    // -----------------------------------------------------------------------
-
     #[test]
-    fn test_initialize() {
+    fn test_ultrahonk_backend_keeps_separate_set() {
         let env = Env::default();
         env.mock_all_auths();
+
         let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
+        let honk_id = env.register(MockUltraHonk, ());
+        let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
-        assert_eq!(pool.admin(), admin);
-        assert_eq!(pool.verifier(), verifier_id);
-    }
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
 
-    #[test]
-    fn test_initialize_twice_fails() {
-        let env = Env::default();
-        env.mock_all_auths();
-        let admin = Address::generate(&env);
-        let verifier_id = env.register(MockVerifier, ());
-        let pool = deploy_pool(&env, &admin, &verifier_id);
+        let commitment = sample_commitment(&env, 0xA9);
+        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::UltraHonk, &None, &None);
+        assert_eq!(res, Err(Ok(PoolError::VerifierNotConfigured)));
 
-        let res = pool.try_initialize(&admin, &verifier_id);
-        assert!(res.is_err());
+        let backend = VerifierBackend::UltraHonk(UltraHonkBackend {
+            verifier: honk_id.clone(),
+            vk_id: Symbol::new(&env, "pool_withdraw"),
+            vk_hash: BytesN::from_array(&env, &[7; 32]),
+        });
+        govern(&env, &pool, GovernanceAction::SetVerifier(backend.clone()));
+        assert_eq!(pool.verifier(&ProofSystem::UltraHonk), Some(backend));
+
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::UltraHonk, &None, &None);
+        assert_eq!(pool.get_deposit(&commitment).unwrap().system, ProofSystem::UltraHonk);
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::UltraHonk), 1);
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 0);
+        let root = pool.get_root(&token, &0, &ProofSystem::UltraHonk);
+
+        // A Groth16 proof cannot spend from the UltraHonk set.
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF3), &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::UnknownRoot)));
+
+        let nullifier_hash = sample_commitment(&env, 0xF3);
+        let proof = WithdrawProof::UltraHonk(Bytes::from_array(&env, &[4u8; 64]));
+        pool.withdraw(&token, &100_000_000_i128, &root, &proof, &nullifier_hash, &direct(&recipient));
+
+        let statement = withdraw_statement(&env, &pool, 100_000_000, &root, &nullifier_hash, None, &direct(&recipient));
+        let mut packed = Bytes::new(&env);
+        for input in statement.iter() {
+            packed.append(&input.into());
+        }
+        assert_eq!(MockUltraHonkClient::new(&env, &honk_id).last_inputs(), packed);
+        assert_eq!(token::Client::new(&env, &token).balance(&recipient), 100_000_000);
     }
 
     #[test]
-    fn test_deposit_records_commitment() {
+    fn test_ultrahonk_backend_rejects_unpinned_vk() {
         let env = Env::default();
         env.mock_all_auths();
-        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
+        let honk_id = env.register(MockUltraHonk, ());
         let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
+        let mut backend = UltraHonkBackend {
+            verifier: honk_id.clone(),
+            vk_id: Symbol::new(&env, "pool_withdraw"),
+            vk_hash: BytesN::from_array(&env, &[7; 32]),
+        };
+        govern(&env, &pool, GovernanceAction::SetVerifier(VerifierBackend::UltraHonk(backend.clone())));
+        list_token(&pool, &token);
         mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0xAB), &ProofSystem::UltraHonk, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::UltraHonk);
+        let proof = WithdrawProof::UltraHonk(Bytes::from_array(&env, &[4u8; 64]));
 
-        let commitment = sample_commitment(&env, 0x01);
-        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment);
+        // The verifier admin re-registers the withdraw VK
+        let swapped = BytesN::from_array(&env, &[8; 32]);
+        MockUltraHonkClient::new(&env, &honk_id).set_vk_hash(&swapped);
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &proof, &sample_commitment(&env, 0xF5), &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::VerifierKeyMismatch)));
 
-        let rec = pool.get_deposit(&commitment).expect("deposit should exist");
-        assert_eq!(rec.amount, 100_000_000);
-        assert_eq!(rec.token, token);
-        assert_eq!(rec.depositor, depositor);
+        // The new key is only accepted once governance pins it
+        backend.vk_hash = swapped;
+        govern(&env, &pool, GovernanceAction::SetVerifier(VerifierBackend::UltraHonk(backend)));
+        pool.withdraw(&token, &100_000_000_i128, &root, &proof, &sample_commitment(&env, 0xF5), &direct(&recipient));
+        assert_eq!(token::Client::new(&env, &token).balance(&recipient), 100_000_000);
     }
 
     #[test]
-    fn test_duplicate_commitment_rejected() {
+    fn test_risc0_backend_binds_statement_in_claim() {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
+        let risc0_id = env.register(MockRisc0, ());
         let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
-        mint(&env, &token, &depositor, 2_000_000_000);
+        let image_id = BytesN::from_array(&env, &[0x11; 32]);
+        let backend = Risc0Backend {
+            verifier: risc0_id.clone(),
+            image_id: image_id.clone(),
+            control_root_0: sample_commitment(&env, 0x21),
+            control_root_1: sample_commitment(&env, 0x22),
+            bn254_control_id: sample_commitment(&env, 0x23),
+        };
+        govern(&env, &pool, GovernanceAction::SetVerifier(VerifierBackend::Risc0(backend.clone())));
 
-        let commitment = sample_commitment(&env, 0x02);
-        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment);
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0xAA), &ProofSystem::Risc0, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Risc0);
 
-        let res = pool.try_deposit(&depositor, &token, &100_000_000_i128, &commitment);
-        assert!(res.is_err());
+        let nullifier_hash = sample_commitment(&env, 0xF4);
+        let proof = WithdrawProof::Risc0(sample_groth16(&env));
+        pool.withdraw(&token, &100_000_000_i128, &root, &proof, &nullifier_hash, &direct(&recipient));
+
+        let statement = withdraw_statement(&env, &pool, 100_000_000, &root, &nullifier_hash, None, &direct(&recipient));
+        let claim = backend::claim_digest(&env, &image_id, &backend::pack(&env, &statement));
+        let (claim_0, claim_1) = backend::split_digest(&env, &claim);
+        // Verification leaves an attestation in the receipt verifier, owned
+        // by the pool itself
+        let (owner, recorded_claim, inputs) = MockRisc0Client::new(&env, &risc0_id).last_inputs();
+        assert_eq!(owner, pool.address);
+        assert_eq!(recorded_claim, claim);
+        assert_eq!(
+            inputs,
+            Vec::from_array(
+                &env,
+                [backend.control_root_0, backend.control_root_1, claim_0, claim_1, backend.bn254_control_id]
+            )
+        );
     }
 
     #[test]
-    fn test_deposit_and_withdraw_full_flow() {
+    fn test_disclosure_proof_binds_deposit_nullifier_and_auditor() {
         let env = Env::default();
         env.mock_all_auths();
-        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
-        let recipient = Address::generate(&env); // completely different address!
+        let recipient = Address::generate(&env);
+        let auditor = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
+        let disclosure_id = env.register(PinnedVerifier, ());
         let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
+        list_token(&pool, &token);
         mint(&env, &token, &depositor, 1_000_000_000);
 
-        let commitment = sample_commitment(&env, 0x03);
-        let nullifier_hash = sample_commitment(&env, 0xAA);
-        let amount: i128 = 250_000_000;
+        let commitment = sample_commitment(&env, 0xAB);
+        let nullifier_hash = sample_commitment(&env, 0xF5);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
 
-        pool.deposit(&depositor, &token, &amount, &commitment);
+        let res = pool.try_verify_disclosure(&commitment, &nullifier_hash, &auditor, &sample_proof(&env));
+        assert_eq!(res, Err(Ok(PoolError::NullifierNotSpent)));
 
-        // The KEY insight: recipient is different from depositor — unlinked!
-        let proof = sample_proof(&env);
-        pool.withdraw(&commitment, &proof, &nullifier_hash, &recipient);
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
 
-        let token_client = token::Client::new(&env, &token);
-        assert_eq!(token_client.balance(&recipient), amount);
-        // depositor did not receive anything
-        assert_eq!(token_client.balance(&depositor), 1_000_000_000 - amount);
+        let res = pool.try_verify_disclosure(&commitment, &nullifier_hash, &auditor, &sample_proof(&env));
+        assert_eq!(res, Err(Ok(PoolError::VerifierNotConfigured)));
+
+        govern(&env, &pool, GovernanceAction::SetDisclosureVerifier(VerifierBackend::Groth16(disclosure_id.clone())));
+        let statement = Vec::from_array(
+            &env,
+            [commitment.clone(), nullifier_hash.clone(), pool.auditor_hash(&auditor)],
+        );
+        PinnedVerifierClient::new(&env, &disclosure_id).pin(&statement);
+
+        assert!(pool.verify_disclosure(&commitment, &nullifier_hash, &auditor, &sample_proof(&env)));
+
+        // The same proof does not verify for another auditor.
+        let other = Address::generate(&env);
+        let res = pool.try_verify_disclosure(&commitment, &nullifier_hash, &other, &sample_proof(&env));
+        assert_eq!(res, Err(Ok(PoolError::InvalidProof)));
+    }
+
+    fn transfer_output(env: &Env, seed: u8) -> TransferOutput {
+        TransferOutput {
+            leaf: sample_commitment(env, seed),
+            memo: Some(Bytes::from_array(env, &[seed; 8])),
+        }
     }
 
     #[test]
-    fn test_double_spend_rejected() {
+    fn test_transfer_spends_inputs_and_appends_outputs() {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
-        let recipient = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
+        let transfer_id = env.register(RecordingVerifier, ());
         let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
+        list_token(&pool, &token);
         mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x31), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
 
-        let commitment = sample_commitment(&env, 0x04);
-        let nullifier_hash = sample_commitment(&env, 0xBB);
-        let proof = sample_proof(&env);
+        let nullifier_hash = sample_commitment(&env, 0xB1);
+        let nullifiers = Vec::from_array(&env, [nullifier_hash.clone()]);
+        let outputs = Vec::from_array(&env, [transfer_output(&env, 0x32), transfer_output(&env, 0x33)]);
 
-        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment);
-        pool.withdraw(&commitment, &proof, &nullifier_hash, &recipient);
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &nullifiers, &outputs);
+        assert_eq!(res, Err(Ok(PoolError::VerifierNotConfigured)));
 
+        govern(&env, &pool, GovernanceAction::SetTransferVerifier(VerifierBackend::Groth16(transfer_id.clone())));
+        let leaves = pool.transfer(&token, &root, &sample_proof(&env), &nullifiers, &outputs);
+        assert_eq!(leaves, Vec::from_array(&env, [1u32, 2u32]));
         assert!(pool.is_nullifier_used(&nullifier_hash));
+        assert_eq!(pool.commitment_count(&token, &0, &ProofSystem::Groth16), 3);
+        // Value stays in the pool
+        assert_eq!(pool.escrow(&token), 100_000_000);
 
-        // Same nullifier — must fail
-        let res = pool.try_withdraw(&commitment, &proof, &nullifier_hash, &recipient);
-        assert!(res.is_err());
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        let inputs = RecordingVerifierClient::new(&env, &transfer_id).last_inputs();
+        assert_eq!(
+            inputs,
+            Vec::from_array(
+                &env,
+                [
+                    root.clone(),
+                    nullifier_hash,
+                    zero,
+                    sample_commitment(&env, 0x32),
+                    sample_commitment(&env, 0x33),
+                    pool.transfer_outputs_hash(&outputs),
+                    pool.domain(),
+                ],
+            )
+        );
+
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &nullifiers, &Vec::from_array(&env, [transfer_output(&env, 0x34)]));
+        assert_eq!(res, Err(Ok(PoolError::NullifierAlreadySpent)));
     }
 
     #[test]
-    fn test_invalid_proof_rejected() {
+    fn test_transfer_rejects_malformed_notes() {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
-        let recipient = Address::generate(&env);
-        let verifier_id = env.register(RejectVerifier, ());
+        let verifier_id = env.register(MockVerifier, ());
         let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
+        list_token(&pool, &token);
         mint(&env, &token, &depositor, 1_000_000_000);
+        let deposited = sample_commitment(&env, 0x41);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &deposited, &ProofSystem::Groth16, &None, &None);
+        govern(&env, &pool, GovernanceAction::SetTransferVerifier(VerifierBackend::Groth16(verifier_id.clone())));
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
 
-        let commitment = sample_commitment(&env, 0x05);
-        let nullifier_hash = sample_commitment(&env, 0xCC);
-        let proof = sample_proof(&env);
+        let one = Vec::from_array(&env, [sample_commitment(&env, 0xC1)]);
+        let output = Vec::from_array(&env, [transfer_output(&env, 0x42)]);
 
-        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment);
+        let three = Vec::from_array(
+            &env,
+            [transfer_output(&env, 0x42), transfer_output(&env, 0x43), transfer_output(&env, 0x44)],
+        );
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &one, &three);
+        assert_eq!(res, Err(Ok(PoolError::InvalidNoteCount)));
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &Vec::new(&env), &output);
+        assert_eq!(res, Err(Ok(PoolError::InvalidNoteCount)));
 
-        let res = pool.try_withdraw(&commitment, &proof, &nullifier_hash, &recipient);
-        assert!(res.is_err());
+        let twice = Vec::from_array(&env, [sample_commitment(&env, 0xC1), sample_commitment(&env, 0xC1)]);
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &twice, &output);
+        assert_eq!(res, Err(Ok(PoolError::NullifierAlreadySpent)));
 
-        // Nullifier must NOT be marked used on failed withdrawal
-        assert!(!pool.is_nullifier_used(&nullifier_hash));
+        let padding = Vec::from_array(&env, [BytesN::from_array(&env, &[0u8; 32])]);
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &padding, &output);
+        assert_eq!(res, Err(Ok(PoolError::InvalidNullifierHash)));
+
+        let repeated = Vec::from_array(&env, [transfer_output(&env, 0x42), transfer_output(&env, 0x42)]);
+        let res = pool.try_transfer(&token, &root, &sample_proof(&env), &one, &repeated);
+        assert_eq!(res, Err(Ok(PoolError::CommitmentAlreadyDeposited)));
+
+        let res = pool.try_transfer(&token, &sample_commitment(&env, 0x99), &sample_proof(&env), &one, &output);
+        assert_eq!(res, Err(Ok(PoolError::UnknownRoot)));
     }
 
     #[test]
-    fn test_withdraw_unknown_commitment_fails() {
+    fn test_transfer_unsupported_in_fixed_mode() {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
-        let recipient = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
-        let pool = deploy_pool(&env, &admin, &verifier_id);
-
-        let commitment = sample_commitment(&env, 0x06);
-        let nullifier_hash = sample_commitment(&env, 0xDD);
-        let proof = sample_proof(&env);
+        let token = create_token(&env, &admin);
+        let pool = deploy_fixed_pool(&env, &admin, &verifier_id);
 
-        let res = pool.try_withdraw(&commitment, &proof, &nullifier_hash, &recipient);
-        assert!(res.is_err());
+        let res = pool.try_transfer(
+            &token,
+            &sample_commitment(&env, 0x01),
+            &sample_proof(&env),
+            &Vec::from_array(&env, [sample_commitment(&env, 0xD1)]),
+            &Vec::from_array(&env, [transfer_output(&env, 0x51)]),
+        );
+        assert_eq!(res, Err(Ok(PoolError::UnsupportedInPoolMode)));
     }
 
     #[test]
-    fn test_zero_amount_deposit_rejected() {
+    fn test_min_note_age_gates_spends() {
         let env = Env::default();
         env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 10_000);
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
         let verifier_id = env.register(MockVerifier, ());
         let token = create_token(&env, &admin);
         let pool = deploy_pool(&env, &admin, &verifier_id);
 
-        let commitment = sample_commitment(&env, 0x07);
-        let res = pool.try_deposit(&depositor, &token, &0_i128, &commitment);
-        assert!(res.is_err());
+        let res = pool.try_set_min_note_age(&(MAX_MIN_NOTE_AGE + 1));
+        assert_eq!(res, Err(Ok(PoolError::InvalidNoteAge)));
+        pool.set_min_note_age(&3_600);
+        assert_eq!(pool.min_note_age(), 3_600);
+
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        let commitment = sample_commitment(&env, 0x61);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        assert_eq!(pool.earliest_withdrawal(&commitment), Some(13_600));
+        assert_eq!(pool.earliest_withdrawal(&sample_commitment(&env, 0x62)), None);
+
+        let nullifier_hash = sample_commitment(&env, 0xE1);
+        env.ledger().with_mut(|l| l.timestamp = 13_599);
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::NoteTooYoung)));
+
+        env.ledger().with_mut(|l| l.timestamp = 13_600);
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+        assert!(pool.is_nullifier_used(&nullifier_hash));
     }
 
     #[test]
-    fn test_i128_to_bytes32_encoding() {
+    fn test_checkpoint_outlives_root_history_flood() {
         let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.timestamp = 10_000);
 
-        // 1 stroop → last byte = 1, rest = 0
-        let b = CommitmentPool::i128_to_bytes32(&env, 1);
-        let arr = b.to_array();
-        assert_eq!(arr[31], 1u8);
-        for i in 0..31 {
-            assert_eq!(arr[i], 0u8);
-        }
+        let admin = Address::generate(&env);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        pool.set_min_note_age(&3_600);
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        let flood = |seed: u8| {
+            for i in 0..ROOT_HISTORY_SIZE {
+                let commitment = sample_commitment(&env, seed + i as u8);
+                pool.deposit(&depositor, &token, &1_000_i128, &commitment, &ProofSystem::Groth16, &None, &None);
+            }
+        };
 
-        // 256 = 0x100 → arr[30]=1, arr[31]=0
-        let b256 = CommitmentPool::i128_to_bytes32(&env, 256);
-        let arr256 = b256.to_array();
-        assert_eq!(arr256[30], 1u8);
-        assert_eq!(arr256[31], 0u8);
-    }
+        // The first note's root becomes the pending checkpoint, so a flood
+        // of deposits cannot evict it.
+        let first = sample_commitment(&env, 0x01);
+        pool.deposit(&depositor, &token, &1_000_i128, &first, &ProofSystem::Groth16, &None, &None);
+        let first_root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        env.ledger().with_mut(|l| l.timestamp = 10_100);
+        flood(0x20);
+        assert!(pool.is_known_root(&token, &0, &ProofSystem::Groth16, &first_root));
+        assert_eq!(pool.earliest_withdrawal(&first), Some(13_600));
 
-    // -----------------------------------------------------------------------
-    // Admin / governance tests (Gate 5 — Auth)
-    // -----------------------------------------------------------------------
+        // A later note flooded out of the history waits for the next
+        // checkpoint, at most about twice the minimum age.
+        let second = sample_commitment(&env, 0x02);
+        pool.deposit(&depositor, &token, &1_000_i128, &second, &ProofSystem::Groth16, &None, &None);
+        flood(0x40);
+        env.ledger().with_mut(|l| l.timestamp = 13_600);
+        pool.withdraw(&token, &1_000_i128, &first_root, &sample_proof(&env), &sample_commitment(&env, 0xE1), &direct(&recipient));
+        flood(0x60);
+        let checkpoints = pool.root_checkpoints(&token, &0, &ProofSystem::Groth16);
+        assert_eq!(checkpoints.get(0).unwrap().root, first_root);
+        let pending = checkpoints.get(1).unwrap();
+        assert_eq!((pending.time, pending.size), (13_600, 2 * ROOT_HISTORY_SIZE + 3));
+        assert_eq!(pool.earliest_withdrawal(&second), Some(17_200));
+
+        env.ledger().with_mut(|l| l.timestamp = 17_199);
+        let res = pool.try_withdraw(&token, &1_000_i128, &pending.root, &sample_proof(&env), &sample_commitment(&env, 0xE2), &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::NoteTooYoung)));
+        env.ledger().with_mut(|l| l.timestamp = 17_200);
+        pool.withdraw(&token, &1_000_i128, &pending.root, &sample_proof(&env), &sample_commitment(&env, 0xE2), &direct(&recipient));
+    }
 
-    /// Admin can replace the verifier address. The new verifier is used for
-    /// all subsequent withdrawals. This is a critical governance operation:
-    /// once replaced, all pending withdrawal proofs must be valid under the
-    /// new verifier's key. Audit note: verifier replacement is admin-only
-    /// and must be disclosed to users — see docs/audits/discombobulator-pr117/THREAT_MODEL.md.
     #[test]
-    fn test_admin_can_replace_verifier() {
+    fn test_renounce_admin_makes_pool_immutable() {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
-        let verifier_a = env.register(MockVerifier, ());
-        let verifier_b = env.register(MockVerifier, ());
-        let pool = deploy_pool(&env, &admin, &verifier_a);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let other_verifier = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        list_token(&pool, &token);
+        assert!(!pool.is_immutable());
+
+        let action = GovernanceAction::SetVerifier(VerifierBackend::Groth16(other_verifier.clone()));
+        pool.propose(&action);
+        let res = pool.try_renounce_admin();
+        assert_eq!(res, Err(Ok(PoolError::ProposalPending)));
+        pool.cancel_proposal();
+
+        pool.renounce_admin();
+        assert!(pool.is_immutable());
+        assert_eq!(pool.try_admin(), Err(Ok(PoolError::AdminRenounced)));
+        assert_eq!(pool.try_propose(&action), Err(Ok(PoolError::AdminRenounced)));
+        assert_eq!(pool.try_execute_proposal(), Err(Ok(PoolError::AdminRenounced)));
+        assert_eq!(pool.try_renounce_admin(), Err(Ok(PoolError::AdminRenounced)));
+        assert_eq!(
+            pool.try_initialize(&admin, &other_verifier, &PoolMode::Open),
+            Err(Ok(PoolError::AlreadyInitialized))
+        );
+        assert_eq!(
+            pool.verifier(&ProofSystem::Groth16),
+            Some(VerifierBackend::Groth16(verifier_id))
+        );
 
-        assert_eq!(pool.verifier(), verifier_a);
-        pool.set_verifier(&verifier_b);
-        assert_eq!(pool.verifier(), verifier_b);
+        // Notes keep flowing
+        mint(&env, &token, &depositor, 1_000_000_000);
+        pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, 0x71), &ProofSystem::Groth16, &None, &None);
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF1), &direct(&recipient));
     }
 
-    /// After the verifier is replaced with a reject-all verifier, all
-    /// withdrawal proofs must fail — even for deposits made before the switch.
-    /// This confirms that verifier replacement is an effective governance lever
-    /// over all outstanding escrowed funds. Users must trust the admin.
     #[test]
-    fn test_verifier_replacement_gates_future_withdrawals() {
+    fn test_protocol_fee_routed_to_treasury() {
         let env = Env::default();
         env.mock_all_auths();
-        env.ledger().with_mut(|l| l.timestamp = 1_000_000);
 
         let admin = Address::generate(&env);
         let depositor = Address::generate(&env);
         let recipient = Address::generate(&env);
-        let verifier_ok = env.register(MockVerifier, ());
-        let verifier_reject = env.register(RejectVerifier, ());
+        let relayer = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
         let token = create_token(&env, &admin);
-        let pool = deploy_pool(&env, &admin, &verifier_ok);
-
-        mint(&env, &token, &depositor, 1_000_000_000);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        let token_client = token::Client::new(&env, &token);
 
-        let commitment = sample_commitment(&env, 0x08);
-        let nullifier_hash = sample_commitment(&env, 0xEE);
-        let proof = sample_proof(&env);
+        let res = pool.try_propose(&GovernanceAction::SetProtocolFee(token.clone(), 50));
+        assert_eq!(res, Err(Ok(PoolError::TreasuryNotSet)));
+        pool.set_treasury(&treasury);
+        let res = pool.try_propose(&GovernanceAction::SetProtocolFee(token.clone(), MAX_PROTOCOL_FEE_BPS + 1));
+        assert_eq!(res, Err(Ok(PoolError::InvalidFeeRate)));
+        govern(&env, &pool, GovernanceAction::SetProtocolFee(token.clone(), 50));
+        assert_eq!(pool.protocol_fee(&token), 50);
 
-        pool.deposit(&depositor, &token, &100_000_000_i128, &commitment);
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        for seed in [0x81, 0x82] {
+            pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, seed), &ProofSystem::Groth16, &None, &None);
+        }
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
 
-        // Admin replaces verifier with one that always rejects
-        pool.set_verifier(&verifier_reject);
+        // The relayer fee must leave room for the protocol fee.
+        let greedy = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: 99_600_000,
+        };
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF3), &greedy);
+        assert_eq!(res, Err(Ok(PoolError::InvalidFee)));
 
-        // Withdrawal now fails — the escrowed deposit is frozen until
-        // a valid verifier is restored (or the admin acts)
-        let res = pool.try_withdraw(&commitment, &proof, &nullifier_hash, &recipient);
-        assert!(res.is_err());
+        let ext_data = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: 1_000_000,
+        };
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF3), &ext_data);
+        assert_eq!(token_client.balance(&treasury), 500_000);
+        assert_eq!(token_client.balance(&relayer), 1_000_000);
+        assert_eq!(token_client.balance(&recipient), 98_500_000);
+        assert_eq!(pool.fees_collected(&token), 500_000);
+        assert_eq!(pool.escrow(&token), 100_000_000);
+        assert!(pool.check_solvency(&token).solvent);
 
-        // Nullifier must NOT be marked used when proof fails post-verifier-swap
+        // A fee with no treasury to receive it fails cleanly, with no note
+        // spent.
+        env.as_contract(&pool.address, || env.storage().instance().remove(&DataKey::Treasury));
+        let nullifier_hash = sample_commitment(&env, 0xF4);
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &nullifier_hash, &direct(&recipient));
+        assert_eq!(res, Err(Ok(PoolError::TreasuryNotSet)));
         assert!(!pool.is_nullifier_used(&nullifier_hash));
     }
 
-    /// withdraw_statement_version() exposes the current statement schema version
-    /// so clients can detect version drift before submitting proofs.
-    #[test]
-    fn test_withdraw_statement_version_is_v1() {
-        let env = Env::default();
-        let pool_id = env.register(CommitmentPool, ());
-        let pool = CommitmentPoolClient::new(&env, &pool_id);
-        assert_eq!(pool.withdraw_statement_version(), 1u32);
-    }
-
-    /// Verifier is readable before and after replacement via the public
-    /// `verifier()` read-only function — callers can detect governance changes.
     #[test]
-    fn test_verifier_address_is_readable() {
+    fn test_protocol_fee_raise_applies_after_governance_delay() {
         let env = Env::default();
         env.mock_all_auths();
 
         let admin = Address::generate(&env);
-        let verifier_a = env.register(MockVerifier, ());
-        let verifier_b = env.register(MockVerifier, ());
-        let pool = deploy_pool(&env, &admin, &verifier_a);
+        let depositor = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let relayer = Address::generate(&env);
+        let verifier_id = env.register(MockVerifier, ());
+        let token = create_token(&env, &admin);
+        let pool = deploy_pool(&env, &admin, &verifier_id);
+        pool.set_treasury(&Address::generate(&env));
+        govern(&env, &pool, GovernanceAction::SetProtocolFee(token.clone(), 50));
 
-        assert_eq!(pool.verifier(), verifier_a);
-        pool.set_verifier(&verifier_b);
-        assert_eq!(pool.verifier(), verifier_b);
-        // admin unchanged
-        assert_eq!(pool.admin(), admin);
+        list_token(&pool, &token);
+        mint(&env, &token, &depositor, 1_000_000_000);
+        for seed in [0x91, 0x92, 0x93] {
+            pool.deposit(&depositor, &token, &100_000_000_i128, &sample_commitment(&env, seed), &ProofSystem::Groth16, &None, &None);
+        }
+        let root = pool.get_root(&token, &0, &ProofSystem::Groth16);
+
+        // The relayer takes everything the 0.5% protocol fee leaves
+        let relayed = ExtData {
+            recipient: recipient.clone(),
+            relayer: Some(relayer.clone()),
+            fee: 99_500_000,
+        };
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF6), &relayed);
+
+        // A raise is announced and the old rate holds until it executes
+        let proposal = pool.propose(&GovernanceAction::SetProtocolFee(token.clone(), 100));
+        assert_eq!(proposal.executable_at, env.ledger().timestamp() + GOVERNANCE_DELAY);
+        pool.withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF7), &relayed);
+        assert_eq!(pool.protocol_fee(&token), 50);
+
+        env.ledger().with_mut(|l| l.timestamp = proposal.executable_at);
+        pool.execute_proposal();
+        assert_eq!(pool.protocol_fee(&token), 100);
+        // The same relayer fee no longer leaves room for the protocol fee
+        let res = pool.try_withdraw(&token, &100_000_000_i128, &root, &sample_proof(&env), &sample_commitment(&env, 0xF8), &relayed);
+        assert_eq!(res, Err(Ok(PoolError::InvalidFee)));
+        assert_eq!(token::Client::new(&env, &token).balance(&relayer), 199_000_000);
     }
 }
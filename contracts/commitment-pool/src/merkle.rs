@@ -0,0 +1,363 @@
+//! Incremental Poseidon Merkle tree of note leaves.
+//!
+//! Tornado-style "filled subtrees" construction: only the rightmost filled
+//! node of each level is kept on-chain, so an insert costs one
+//! Poseidon(left, right) per level and a constant amount of storage.
+//! Clients rebuild the full tree off-chain from `Deposited`, `ChangeNote`
+//! and `TransferNote` events (which carry the leaf and its index) to
+//! produce authentication paths.
+//!
+//! Hashing matches circomlib `Poseidon(2)` over BN254, so the same tree can
+//! be recomputed inside a circom membership circuit.
+
+use soroban_poseidon::PoseidonSponge;
+use soroban_sdk::{contracttype, crypto::BnScalar, vec, Bytes, BytesN, Env, Vec, U256};
+
+/// Tree depth. 2^20 ≈ 1M leaves per commitment set.
+pub const TREE_DEPTH: u32 = 20;
+
+/// Number of recent roots accepted by `withdraw`. A proof built against a
+/// root stays valid while up to `ROOT_HISTORY_SIZE - 1` later deposits land.
+pub const ROOT_HISTORY_SIZE: u32 = 30;
+
+/// BN254 scalar field modulus, big-endian.
+const FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29,
+    0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91,
+    0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+/// ZEROS[i] is the root of an empty subtree of height i, where an empty
+/// leaf is 0 and ZEROS[i + 1] = Poseidon(ZEROS[i], ZEROS[i]).
+const ZEROS: [[u8; 32]; TREE_DEPTH as usize + 1] = [
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0x20, 0x98, 0xf5, 0xfb, 0x9e, 0x23, 0x9e, 0xab,
+        0x3c, 0xea, 0xc3, 0xf2, 0x7b, 0x81, 0xe4, 0x81,
+        0xdc, 0x31, 0x24, 0xd5, 0x5f, 0xfe, 0xd5, 0x23,
+        0xa8, 0x39, 0xee, 0x84, 0x46, 0xb6, 0x48, 0x64,
+    ],
+    [
+        0x10, 0x69, 0x67, 0x3d, 0xcd, 0xb1, 0x22, 0x63,
+        0xdf, 0x30, 0x1a, 0x6f, 0xf5, 0x84, 0xa7, 0xec,
+        0x26, 0x1a, 0x44, 0xcb, 0x9d, 0xc6, 0x8d, 0xf0,
+        0x67, 0xa4, 0x77, 0x44, 0x60, 0xb1, 0xf1, 0xe1,
+    ],
+    [
+        0x18, 0xf4, 0x33, 0x31, 0x53, 0x7e, 0xe2, 0xaf,
+        0x2e, 0x3d, 0x75, 0x8d, 0x50, 0xf7, 0x21, 0x06,
+        0x46, 0x7c, 0x6e, 0xea, 0x50, 0x37, 0x1d, 0xd5,
+        0x28, 0xd5, 0x7e, 0xb2, 0xb8, 0x56, 0xd2, 0x38,
+    ],
+    [
+        0x07, 0xf9, 0xd8, 0x37, 0xcb, 0x17, 0xb0, 0xd3,
+        0x63, 0x20, 0xff, 0xe9, 0x3b, 0xa5, 0x23, 0x45,
+        0xf1, 0xb7, 0x28, 0x57, 0x1a, 0x56, 0x82, 0x65,
+        0xca, 0xac, 0x97, 0x55, 0x9d, 0xbc, 0x95, 0x2a,
+    ],
+    [
+        0x2b, 0x94, 0xcf, 0x5e, 0x87, 0x46, 0xb3, 0xf5,
+        0xc9, 0x63, 0x1f, 0x4c, 0x5d, 0xf3, 0x29, 0x07,
+        0xa6, 0x99, 0xc5, 0x8c, 0x94, 0xb2, 0xad, 0x4d,
+        0x7b, 0x5c, 0xec, 0x16, 0x39, 0x18, 0x3f, 0x55,
+    ],
+    [
+        0x2d, 0xee, 0x93, 0xc5, 0xa6, 0x66, 0x45, 0x96,
+        0x46, 0xea, 0x7d, 0x22, 0xcc, 0xa9, 0xe1, 0xbc,
+        0xfe, 0xd7, 0x1e, 0x69, 0x51, 0xb9, 0x53, 0x61,
+        0x1d, 0x11, 0xdd, 0xa3, 0x2e, 0xa0, 0x9d, 0x78,
+    ],
+    [
+        0x07, 0x82, 0x95, 0xe5, 0xa2, 0x2b, 0x84, 0xe9,
+        0x82, 0xcf, 0x60, 0x1e, 0xb6, 0x39, 0x59, 0x7b,
+        0x8b, 0x05, 0x15, 0xa8, 0x8c, 0xb5, 0xac, 0x7f,
+        0xa8, 0xa4, 0xaa, 0xbe, 0x3c, 0x87, 0x34, 0x9d,
+    ],
+    [
+        0x2f, 0xa5, 0xe5, 0xf1, 0x8f, 0x60, 0x27, 0xa6,
+        0x50, 0x1b, 0xec, 0x86, 0x45, 0x64, 0x47, 0x2a,
+        0x61, 0x6b, 0x2e, 0x27, 0x4a, 0x41, 0x21, 0x1a,
+        0x44, 0x4c, 0xbe, 0x3a, 0x99, 0xf3, 0xcc, 0x61,
+    ],
+    [
+        0x0e, 0x88, 0x43, 0x76, 0xd0, 0xd8, 0xfd, 0x21,
+        0xec, 0xb7, 0x80, 0x38, 0x9e, 0x94, 0x1f, 0x66,
+        0xe4, 0x5e, 0x7a, 0xcc, 0xe3, 0xe2, 0x28, 0xab,
+        0x3e, 0x21, 0x56, 0xa6, 0x14, 0xfc, 0xd7, 0x47,
+    ],
+    [
+        0x1b, 0x72, 0x01, 0xda, 0x72, 0x49, 0x4f, 0x1e,
+        0x28, 0x71, 0x7a, 0xd1, 0xa5, 0x2e, 0xb4, 0x69,
+        0xf9, 0x58, 0x92, 0xf9, 0x57, 0x71, 0x35, 0x33,
+        0xde, 0x61, 0x75, 0xe5, 0xda, 0x19, 0x0a, 0xf2,
+    ],
+    [
+        0x1f, 0x8d, 0x88, 0x22, 0x72, 0x5e, 0x36, 0x38,
+        0x52, 0x00, 0xc0, 0xb2, 0x01, 0x24, 0x98, 0x19,
+        0xa6, 0xe6, 0xe1, 0xe4, 0x65, 0x08, 0x08, 0xb5,
+        0xbe, 0xbc, 0x6b, 0xfa, 0xce, 0x7d, 0x76, 0x36,
+    ],
+    [
+        0x2c, 0x5d, 0x82, 0xf6, 0x6c, 0x91, 0x4b, 0xaf,
+        0xb9, 0x70, 0x15, 0x89, 0xba, 0x8c, 0xfc, 0xfb,
+        0x61, 0x62, 0xb0, 0xa1, 0x2a, 0xcf, 0x88, 0xa8,
+        0xd0, 0x87, 0x9a, 0x04, 0x71, 0xb5, 0xf8, 0x5a,
+    ],
+    [
+        0x14, 0xc5, 0x41, 0x48, 0xa0, 0x94, 0x0b, 0xb8,
+        0x20, 0x95, 0x7f, 0x5a, 0xdf, 0x3f, 0xa1, 0x13,
+        0x4e, 0xf5, 0xc4, 0xaa, 0xa1, 0x13, 0xf4, 0x64,
+        0x64, 0x58, 0xf2, 0x70, 0xe0, 0xbf, 0xbf, 0xd0,
+    ],
+    [
+        0x19, 0x0d, 0x33, 0xb1, 0x2f, 0x98, 0x6f, 0x96,
+        0x1e, 0x10, 0xc0, 0xee, 0x44, 0xd8, 0xb9, 0xaf,
+        0x11, 0xbe, 0x25, 0x58, 0x8c, 0xad, 0x89, 0xd4,
+        0x16, 0x11, 0x8e, 0x4b, 0xf4, 0xeb, 0xe8, 0x0c,
+    ],
+    [
+        0x22, 0xf9, 0x8a, 0xa9, 0xce, 0x70, 0x41, 0x52,
+        0xac, 0x17, 0x35, 0x49, 0x14, 0xad, 0x73, 0xed,
+        0x11, 0x67, 0xae, 0x65, 0x96, 0xaf, 0x51, 0x0a,
+        0xa5, 0xb3, 0x64, 0x93, 0x25, 0xe0, 0x6c, 0x92,
+    ],
+    [
+        0x2a, 0x7c, 0x7c, 0x9b, 0x6c, 0xe5, 0x88, 0x0b,
+        0x9f, 0x6f, 0x22, 0x8d, 0x72, 0xbf, 0x6a, 0x57,
+        0x5a, 0x52, 0x6f, 0x29, 0xc6, 0x6e, 0xcc, 0xee,
+        0xf8, 0xb7, 0x53, 0xd3, 0x8b, 0xba, 0x73, 0x23,
+    ],
+    [
+        0x2e, 0x81, 0x86, 0xe5, 0x58, 0x69, 0x8e, 0xc1,
+        0xc6, 0x7a, 0xf9, 0xc1, 0x4d, 0x46, 0x3f, 0xfc,
+        0x47, 0x00, 0x43, 0xc9, 0xc2, 0x98, 0x8b, 0x95,
+        0x4d, 0x75, 0xdd, 0x64, 0x3f, 0x36, 0xb9, 0x92,
+    ],
+    [
+        0x0f, 0x57, 0xc5, 0x57, 0x1e, 0x9a, 0x4e, 0xab,
+        0x49, 0xe2, 0xc8, 0xcf, 0x05, 0x0d, 0xae, 0x94,
+        0x8a, 0xef, 0x6e, 0xad, 0x64, 0x73, 0x92, 0x27,
+        0x35, 0x46, 0x24, 0x9d, 0x1c, 0x1f, 0xf1, 0x0f,
+    ],
+    [
+        0x18, 0x30, 0xee, 0x67, 0xb5, 0xfb, 0x55, 0x4a,
+        0xd5, 0xf6, 0x3d, 0x43, 0x88, 0x80, 0x0e, 0x1c,
+        0xfe, 0x78, 0xe3, 0x10, 0x69, 0x7d, 0x46, 0xe4,
+        0x3c, 0x9c, 0xe3, 0x61, 0x34, 0xf7, 0x2c, 0xca,
+    ],
+    [
+        0x21, 0x34, 0xe7, 0x6a, 0xc5, 0xd2, 0x1a, 0xab,
+        0x18, 0x6c, 0x2b, 0xe1, 0xdd, 0x8f, 0x84, 0xee,
+        0x88, 0x0a, 0x1e, 0x46, 0xea, 0xf7, 0x12, 0xf9,
+        0xd3, 0x71, 0xb6, 0xdf, 0x22, 0x19, 0x1f, 0x3e,
+    ],
+];
+
+/// A root kept outside the ring buffer, with when it became current and
+/// how many leaves it covers.
+#[derive(Clone)]
+#[contracttype]
+pub struct Checkpoint {
+    pub root: BytesN<32>,
+    pub time: u64,
+    pub size: u32,
+}
+
+/// On-chain state of one incremental tree.
+#[derive(Clone)]
+#[contracttype]
+pub struct MerkleTree {
+    /// Index the next inserted leaf will occupy (= number of leaves).
+    pub next_index: u32,
+    /// Position of the latest root inside `roots`.
+    pub current_root_index: u32,
+    /// Rightmost filled node per level, `TREE_DEPTH` entries.
+    pub filled_subtrees: Vec<BytesN<32>>,
+    /// Ring buffer of the last `ROOT_HISTORY_SIZE` roots.
+    pub roots: Vec<BytesN<32>>,
+    /// Ledger timestamp at which each entry of `roots` became current.
+    pub root_times: Vec<u64>,
+    /// Newest root known to be at least the minimum note age old when
+    /// `pending` was promoted. Outlives the ring buffer, so a burst of
+    /// inserts cannot leave a commitment set without a spendable root.
+    pub checkpoint: Checkpoint,
+    /// Next checkpoint; promoted once it is the minimum note age old.
+    pub pending: Checkpoint,
+}
+
+impl MerkleTree {
+    pub fn new(env: &Env) -> Self {
+        let mut filled_subtrees = Vec::new(env);
+        for level in 0..TREE_DEPTH {
+            filled_subtrees.push_back(zero(env, level));
+        }
+        let empty = Checkpoint {
+            root: zero(env, TREE_DEPTH),
+            time: 0,
+            size: 0,
+        };
+        MerkleTree {
+            next_index: 0,
+            current_root_index: 0,
+            filled_subtrees,
+            roots: vec![env, zero(env, TREE_DEPTH)],
+            root_times: vec![env, 0],
+            checkpoint: empty.clone(),
+            pending: empty,
+        }
+    }
+
+    /// Insert `leaf` and roll the root history. Returns the leaf index, or
+    /// `None` once all 2^TREE_DEPTH slots are used.
+    pub fn insert(&mut self, env: &Env, leaf: &BytesN<32>) -> Option<u32> {
+        if self.next_index >= 1u32 << TREE_DEPTH {
+            return None;
+        }
+        let leaf_index = self.next_index;
+        let mut sponge = PoseidonSponge::<3, BnScalar>::new(env);
+        let mut index = leaf_index;
+        let mut node = leaf.clone();
+        for level in 0..TREE_DEPTH {
+            let (left, right) = if index & 1 == 0 {
+                self.filled_subtrees.set(level, node.clone());
+                (node, zero(env, level))
+            } else {
+                (self.filled_subtrees.get(level).unwrap(), node)
+            };
+            node = hash_pair(env, &mut sponge, &left, &right);
+            index /= 2;
+        }
+
+        let next_root_index = (self.current_root_index + 1) % ROOT_HISTORY_SIZE;
+        let now = env.ledger().timestamp();
+        if self.roots.len() < ROOT_HISTORY_SIZE {
+            self.roots.push_back(node);
+            self.root_times.push_back(now);
+        } else {
+            self.roots.set(next_root_index, node);
+            self.root_times.set(next_root_index, now);
+        }
+        self.current_root_index = next_root_index;
+        self.next_index = leaf_index + 1;
+        Some(leaf_index)
+    }
+
+    pub fn root(&self) -> BytesN<32> {
+        self.roots.get(self.current_root_index).unwrap()
+    }
+
+    /// True if `root` is in the history or is the checkpoint or pending
+    /// root.
+    pub fn is_known_root(&self, root: &BytesN<32>) -> bool {
+        self.root_time(root).is_some()
+    }
+
+    /// When `root` became current, or None if it is neither in the history
+    /// nor the checkpoint or pending root.
+    pub fn root_time(&self, root: &BytesN<32>) -> Option<u64> {
+        if let Some(index) = self.roots.first_index_of(root) {
+            return self.root_times.get(index);
+        }
+        [&self.checkpoint, &self.pending]
+            .into_iter()
+            .find(|c| c.root == *root)
+            .map(|c| c.time)
+    }
+
+    /// Promote `pending` to `checkpoint` once it is `min_age` old, and make
+    /// the current root the new pending one. Called after every insert, so
+    /// checkpoints are spaced at least `min_age` apart and each stays
+    /// accepted until the next one is `min_age` old.
+    pub fn roll_checkpoint(&mut self, env: &Env, min_age: u64) {
+        let now = env.ledger().timestamp();
+        if now >= self.pending.time.saturating_add(min_age) {
+            self.checkpoint = self.pending.clone();
+            self.pending = Checkpoint {
+                root: self.root(),
+                time: now,
+                size: self.next_index,
+            };
+        }
+    }
+
+    /// Creation time of the oldest kept root that covers leaf `leaf_index`
+    /// (< `next_index`). The current root covers every leaf; older history
+    /// roots and the checkpoints cover the leaves inserted before them.
+    pub fn first_root_time_covering(&self, leaf_index: u32) -> u64 {
+        // The root `back` inserts ago covers `next_index - back` leaves.
+        let len = self.roots.len();
+        let back = (self.next_index - leaf_index).min(len) - 1;
+        let index = (self.current_root_index + len - back) % len;
+        let ring = self.root_times.get(index).unwrap();
+        [&self.checkpoint, &self.pending]
+            .into_iter()
+            .filter(|c| c.size > leaf_index)
+            .map(|c| c.time)
+            .fold(ring, u64::min)
+    }
+}
+
+/// True if `value` is a canonical BN254 scalar (strictly below the modulus).
+pub fn is_field_element(value: &BytesN<32>) -> bool {
+    value.to_array() < FIELD_MODULUS
+}
+
+fn zero(env: &Env, level: u32) -> BytesN<32> {
+    BytesN::from_array(env, &ZEROS[level as usize])
+}
+
+fn hash_pair(
+    env: &Env,
+    sponge: &mut PoseidonSponge<3, BnScalar>,
+    left: &BytesN<32>,
+    right: &BytesN<32>,
+) -> BytesN<32> {
+    let inputs = vec![env, to_u256(env, left), to_u256(env, right)];
+    from_u256(&sponge.compute_hash(&inputs))
+}
+
+pub(crate) fn to_u256(env: &Env, value: &BytesN<32>) -> U256 {
+    U256::from_be_bytes(env, &Bytes::from(value))
+}
+
+pub(crate) fn from_u256(value: &U256) -> BytesN<32> {
+    value.to_be_bytes().try_into().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_zero_table_matches_poseidon() {
+        let env = Env::default();
+        let mut sponge = PoseidonSponge::<3, BnScalar>::new(&env);
+        for level in 0..TREE_DEPTH {
+            let z = zero(&env, level);
+            assert_eq!(hash_pair(&env, &mut sponge, &z, &z), zero(&env, level + 1));
+        }
+    }
+
+    #[test]
+    fn test_single_leaf_root() {
+        let env = Env::default();
+        let mut sponge = PoseidonSponge::<3, BnScalar>::new(&env);
+        let leaf = BytesN::from_array(&env, &[7u8; 32]);
+
+        let mut expected = leaf.clone();
+        for level in 0..TREE_DEPTH {
+            expected = hash_pair(&env, &mut sponge, &expected, &zero(&env, level));
+        }
+
+        let mut tree = MerkleTree::new(&env);
+        assert_eq!(tree.root(), zero(&env, TREE_DEPTH));
+        assert_eq!(tree.insert(&env, &leaf), Some(0));
+        assert_eq!(tree.root(), expected);
+    }
+}
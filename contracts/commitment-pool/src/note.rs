@@ -0,0 +1,44 @@
+//! Note hashing performed on-chain.
+//!
+//! Withdrawals never reveal a note opening, so the contract normally only
+//! sees hashes produced by the circuit. Two exceptions: a deposit's tree
+//! leaf is computed here from the amount actually received, and the refund
+//! path has the depositor reveal (secret, nullifier) so the contract
+//! recomputes the commitment. Both use the same circomlib Poseidon hashes
+//! as the circuit.
+
+use soroban_poseidon::poseidon_hash;
+use soroban_sdk::{crypto::BnScalar, vec, BytesN, Env};
+
+use crate::merkle::{from_u256, to_u256};
+
+/// C = Poseidon(secret, amount, nullifier). All three must be canonical
+/// BN254 scalars.
+pub fn commitment(
+    env: &Env,
+    secret: &BytesN<32>,
+    amount: &BytesN<32>,
+    nullifier: &BytesN<32>,
+) -> BytesN<32> {
+    let inputs = vec![
+        env,
+        to_u256(env, secret),
+        to_u256(env, amount),
+        to_u256(env, nullifier),
+    ];
+    from_u256(&poseidon_hash::<4, BnScalar>(env, &inputs))
+}
+
+/// L = Poseidon(commitment, amount), the Merkle leaf of a note. The
+/// circuit opens L, so a note can only be spent for the amount its leaf
+/// was built with.
+pub fn leaf(env: &Env, commitment: &BytesN<32>, amount: &BytesN<32>) -> BytesN<32> {
+    let inputs = vec![env, to_u256(env, commitment), to_u256(env, amount)];
+    from_u256(&poseidon_hash::<3, BnScalar>(env, &inputs))
+}
+
+/// N = Poseidon(nullifier), the value published when a note is spent.
+pub fn nullifier_hash(env: &Env, nullifier: &BytesN<32>) -> BytesN<32> {
+    let inputs = vec![env, to_u256(env, nullifier)];
+    from_u256(&poseidon_hash::<2, BnScalar>(env, &inputs))
+}
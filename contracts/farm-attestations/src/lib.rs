@@ -3,7 +3,7 @@
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype, symbol_short,
     crypto::bn254::{Bn254G1Affine, Bn254G2Affine, Fr},
-    Address, Bytes, BytesN, Env, IntoVal, Symbol, Val, Vec,
+    xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Symbol, TryFromVal, Val, Vec,
 };
 
 // ════════════════════════════════════════════════════════════════════════
@@ -22,8 +22,50 @@ pub enum DataKey {
     SystemIndex(Symbol),
     OwnerIndex(Address),
     Record((Symbol, BytesN<32>)),
+    // v4 ──────────────────────────────────────────────────────────────
+    SystemLen(Symbol),
+    SystemPage((Symbol, u32)),
+    OwnerLen(Address),
+    OwnerPage((Address, u32)),
+    Indexed((Address, Symbol, Symbol)),
+    // v5 ──────────────────────────────────────────────────────────────
+    Validity(Symbol),
+    Issuer(Symbol),
+    Revoked((Address, Symbol, Symbol)),
+    // v6 ──────────────────────────────────────────────────────────────
+    Binding((ProofRoute, Symbol)),
+    BoundSystem(Symbol),
+    Statement((Symbol, BytesN<32>)),
+    // v7 ──────────────────────────────────────────────────────────────
+    Risc0Verifier,
+    Risc0Image(BytesN<32>),
+    Risc0Commitment(BytesN<32>),
+    // v8 ──────────────────────────────────────────────────────────────
+    Groth16VkIds,
+    Groth16VkCount(Symbol),
+    Groth16VkData((Symbol, u32)),
+    Groth16VkMeta((Symbol, u32)),
+    AttestationVk(u64),
 }
 
+/// Length of the risc0-tier journal `(u8, u64, [u8; 32], [u8; 32])` in
+/// RISC Zero's serde encoding: one u32 word for the u8, two for the u64 and
+/// one per byte of the commitment and the owner field.
+pub const RISC0_TIER_JOURNAL_LEN: u32 = 4 + 8 + 32 * 4 * 2;
+
+/// Entries per index page, and the most a single page read returns.
+pub const INDEX_PAGE_SIZE: u32 = 64;
+
+/// The BN254 scalar field order r, big-endian. Verifiers reduce public
+/// inputs mod r, so x and x + r verify alike; only canonical inputs below r
+/// are accepted, which keeps one `statement_hash` per statement.
+pub const BN254_SCALAR_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29,
+    0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91,
+    0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
 // ════════════════════════════════════════════════════════════════════════
 //  Types
 // ════════════════════════════════════════════════════════════════════════
@@ -50,6 +92,97 @@ pub struct Badge {
     pub tier: Symbol,
 }
 
+/// Verified attestation routes. Each has its own binding namespace, so a
+/// binding registered for one route never applies to another.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ProofRoute {
+    /// Groth16 VK from this contract's registry
+    Groth16,
+    /// UltraHonk VK registered with the ultrahonk verifier
+    UltraHonk,
+    /// The ultrahonk verifier's built-in VK
+    UltraHonkDefault,
+}
+
+/// How a verified proof's public inputs map onto an attestation. The
+/// record's `system` is fixed here; `tier` and `score` are read from the
+/// public inputs, so the prover cannot claim anything the circuit did not
+/// prove. The owner is one of those inputs too, so a proof seen in the
+/// mempool cannot be submitted for another address.
+#[derive(Clone)]
+#[contracttype]
+pub struct AttestationBinding {
+    pub system: Symbol,
+    /// Index of the public input holding the tier index into `tiers`
+    pub tier_input: u32,
+    pub tiers: Vec<Symbol>,
+    /// Index of the public input holding the score, if the circuit has one
+    pub score_input: Option<u32>,
+    /// Index of the public input that must equal `owner_field(owner)`
+    pub owner_input: u32,
+    /// Hash of the VK proofs must verify under: the registry version hash
+    /// for `Groth16`, sha256 of the VK bytes for the UltraHonk routes.
+    /// Required for `UltraHonkDefault`, whose VK the verifier admin can
+    /// swap; checked when set for the others.
+    pub vk_hash: Option<BytesN<32>>,
+}
+
+/// Lifecycle of a registered VK version. Only moves forward: a deprecated
+/// version is never reactivated, and a removed one has its key deleted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum VkStatus {
+    Active,
+    /// Kept for reference; no new attestations
+    Deprecated,
+    /// Key deleted; metadata kept so past attestations stay explained
+    Removed,
+}
+
+/// Metadata of one immutable version of a Groth16 VK.
+#[derive(Clone)]
+#[contracttype]
+pub struct VkVersion {
+    pub version: u32,
+    /// sha256 of the VK's XDR; the `verifier_hash` of its attestations
+    pub hash: BytesN<32>,
+    pub status: VkStatus,
+    /// Ledger of registration; 0 for keys registered before versioning
+    pub registered_ledger: u32,
+}
+
+/// The VK version that verified an attestation.
+#[derive(Clone)]
+#[contracttype]
+pub struct VkRef {
+    pub vk_id: Symbol,
+    pub version: u32,
+}
+
+/// A risc0-groth16-verifier contract and the constants of the RISC Zero
+/// release it verifies: the control root, split into two field elements,
+/// and the BN254 control ID, both as the verifier's public inputs expect.
+#[derive(Clone)]
+#[contracttype]
+pub struct Risc0Verifier {
+    pub verifier: Address,
+    pub control_root_0: BytesN<32>,
+    pub control_root_1: BytesN<32>,
+    pub bn254_control_id: BytesN<32>,
+}
+
+/// An allowlisted risc0-tier guest image: the system its receipts attest
+/// to, the tier symbol of each journal `tier_index` and the lowest journal
+/// `threshold` that tier accepts.
+#[derive(Clone)]
+#[contracttype]
+pub struct Risc0Image {
+    pub system: Symbol,
+    pub tiers: Vec<Symbol>,
+    pub min_thresholds: Vec<u64>,
+}
+
 /// Lightweight pointer stored in per-system indexes for enumeration.
 #[derive(Clone)]
 #[contracttype]
@@ -58,6 +191,29 @@ pub struct IndexEntry {
     pub tier: Symbol,
 }
 
+/// Length of a paged index. The first `legacy` positions are the v3
+/// single-entry index, frozen at upgrade; the rest live in pages of
+/// `INDEX_PAGE_SIZE` entries.
+#[derive(Clone)]
+#[contracttype]
+pub struct IndexLen {
+    pub legacy: u32,
+    pub total: u32,
+}
+
+/// Why and when an attestation was revoked. Applies to the attestation
+/// with `attestation_id` only. Until `cleared`, no new attestation of the
+/// same (owner, system, tier) can be written.
+#[derive(Clone)]
+#[contracttype]
+pub struct Revocation {
+    pub attestation_id: u64,
+    pub reason: u32,
+    pub revoker: Address,
+    pub ledger: u32,
+    pub cleared: bool,
+}
+
 /// Global best score for a (system, verifier_hash) combination.
 #[derive(Clone)]
 #[contracttype]
@@ -80,6 +236,16 @@ pub struct OwnerStats {
 //  Errors
 // ════════════════════════════════════════════════════════════════════════
 
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum AttestError {
+    /// The (owner, system, tier) has an uncleared revocation
+    Revoked = 1,
+    /// The system is attested through a verified route only
+    SystemBound = 2,
+}
+
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
@@ -87,6 +253,43 @@ pub enum Groth16Error {
     UnknownVk = 1,
     InvalidPublicInputs = 2,
     InvalidProof = 3,
+    UnknownBinding = 4,
+    InactiveVk = 5,
+    Revoked = 6,
+    VkMismatch = 7,
+    StatementUsed = 8,
+    /// VK status only moves forward: Active -> Deprecated -> Removed
+    InvalidVkStatus = 9,
+    /// The uploaded VK does not hash to the pinned `vk_hash`
+    VkHashMismatch = 10,
+    /// The owner input is not `owner_field(owner)`
+    OwnerMismatch = 11,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Risc0Error {
+    NotConfigured = 1,
+    ImageNotAllowed = 2,
+    InvalidJournal = 3,
+    VerificationFailed = 4,
+    ThresholdTooLow = 5,
+    CommitmentUsed = 6,
+    Revoked = 7,
+    /// The journal's owner field is not `owner_field(owner)`
+    OwnerMismatch = 8,
+    /// `min_thresholds` does not hold one entry per tier
+    ThresholdsMismatch = 9,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RevocationError {
+    NotFound = 1,
+    NotAuthorized = 2,
+    AlreadyRevoked = 3,
 }
 
 #[contracterror]
@@ -95,6 +298,34 @@ pub enum Groth16Error {
 pub enum UltraHonkError {
     NotConfigured = 1,
     VerificationFailed = 2,
+    UnknownBinding = 3,
+    InvalidPublicInputs = 4,
+    Revoked = 5,
+    VkMismatch = 6,
+    StatementUsed = 7,
+    /// The owner input is not `owner_field(owner)`
+    OwnerMismatch = 8,
+}
+
+// `store` fails only on a pending revocation; `SystemBound` is raised by
+// the digest-only entry points before they reach it.
+
+impl From<AttestError> for Groth16Error {
+    fn from(_: AttestError) -> Self {
+        Groth16Error::Revoked
+    }
+}
+
+impl From<AttestError> for Risc0Error {
+    fn from(_: AttestError) -> Self {
+        Risc0Error::Revoked
+    }
+}
+
+impl From<AttestError> for UltraHonkError {
+    fn from(_: AttestError) -> Self {
+        UltraHonkError::Revoked
+    }
 }
 
 // ════════════════════════════════════════════════════════════════════════
@@ -129,7 +360,7 @@ pub struct FarmAttestations;
 #[contractimpl]
 impl FarmAttestations {
     pub fn version() -> u32 {
-        3
+        8
     }
 
     // ── Admin ────────────────────────────────────────────────────────
@@ -171,32 +402,291 @@ impl FarmAttestations {
     }
 
     pub fn extend_entry_ttl(env: Env, owner: Address, system: Symbol, tier: Symbol) {
-        let key = DataKey::Entry((owner, system, tier));
-        if env.storage().persistent().has(&key) {
-            Self::bump_persistent(&env, &key);
+        let id = (owner, system, tier);
+        for key in [DataKey::Entry(id.clone()), DataKey::Indexed(id)] {
+            if env.storage().persistent().has(&key) {
+                Self::bump_persistent(&env, &key);
+            }
+        }
+    }
+
+    /// Extend page `page` of `system`'s index, the index length and the v3
+    /// prefix. Full pages are never written again, so only this keeps them
+    /// live.
+    pub fn extend_system_index_ttl(env: Env, system: Symbol, page: u32) {
+        let keys = [
+            DataKey::SystemLen(system.clone()),
+            DataKey::SystemIndex(system.clone()),
+            DataKey::SystemPage((system, page)),
+        ];
+        for key in keys {
+            if env.storage().persistent().has(&key) {
+                Self::bump_persistent(&env, &key);
+            }
+        }
+    }
+
+    /// Extend page `page` of `owner`'s passport, its length and the v3
+    /// prefix.
+    pub fn extend_owner_index_ttl(env: Env, owner: Address, page: u32) {
+        let keys = [
+            DataKey::OwnerLen(owner.clone()),
+            DataKey::OwnerIndex(owner.clone()),
+            DataKey::OwnerPage((owner, page)),
+        ];
+        for key in keys {
+            if env.storage().persistent().has(&key) {
+                Self::bump_persistent(&env, &key);
+            }
         }
     }
 
+    /// Extend every stored version of `vk_id`.
     pub fn extend_vk_ttl(env: Env, vk_id: Symbol) {
-        let key = DataKey::Groth16Vk(vk_id);
-        if env.storage().persistent().has(&key) {
-            Self::bump_persistent(&env, &key);
+        let mut keys = Vec::new(&env);
+        keys.push_back(DataKey::Groth16Vk(vk_id.clone()));
+        keys.push_back(DataKey::Groth16VkCount(vk_id.clone()));
+        for version in 1..=Self::vk_count(&env, &vk_id) {
+            keys.push_back(DataKey::Groth16VkData((vk_id.clone(), version)));
+            keys.push_back(DataKey::Groth16VkMeta((vk_id.clone(), version)));
+        }
+        for key in keys.iter() {
+            if env.storage().persistent().has(&key) {
+                Self::bump_persistent(&env, &key);
+            }
         }
     }
 
-    // ── Groth16 VK Registry ─────────────────────────────────────────
+    // ── Expiry & Revocation ─────────────────────────────────────────
+
+    /// Records of `system` expire `ledgers` ledgers after the ledger they
+    /// were attested in; 0 means they never expire. Applies to existing
+    /// records too.
+    pub fn set_validity(env: Env, system: Symbol, ledgers: u32) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        env.storage()
+            .instance()
+            .set(&DataKey::Validity(system), &ledgers);
+        Self::bump_instance(&env);
+    }
 
-    pub fn register_groth16_vk(env: Env, vk_id: Symbol, vk: Groth16VerificationKey) {
+    pub fn get_validity(env: Env, system: Symbol) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Validity(system))
+            .unwrap_or(0)
+    }
+
+    /// Allow `issuer` to revoke attestations of `system`, alongside the
+    /// admin.
+    pub fn set_issuer(env: Env, system: Symbol, issuer: Address) {
         let admin = Self::admin(env.clone());
         admin.require_auth();
-        let key = DataKey::Groth16Vk(vk_id);
-        env.storage().persistent().set(&key, &vk);
+        env.storage()
+            .instance()
+            .set(&DataKey::Issuer(system), &issuer);
+        Self::bump_instance(&env);
+    }
+
+    pub fn get_issuer(env: Env, system: Symbol) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Issuer(system))
+    }
+
+    /// Revoke the current attestation of (owner, system, tier) with an
+    /// application-defined `reason` code. `revoker` must be the admin or
+    /// the system's issuer. New attestations of the triple are refused
+    /// until `clear_revocation`, and a world record the attestation held
+    /// is cleared.
+    pub fn revoke(
+        env: Env,
+        revoker: Address,
+        owner: Address,
+        system: Symbol,
+        tier: Symbol,
+        reason: u32,
+    ) -> Result<(), RevocationError> {
+        Self::require_revoker(&env, &revoker, &system)?;
+        let record: AttestationRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entry((owner.clone(), system.clone(), tier.clone())))
+            .ok_or(RevocationError::NotFound)?;
+        if Self::is_revoked(&env, &record) {
+            return Err(RevocationError::AlreadyRevoked);
+        }
+
+        let ledger = env.ledger().sequence();
+        let key = DataKey::Revoked((owner.clone(), system.clone(), tier.clone()));
+        env.storage().persistent().set(
+            &key,
+            &Revocation {
+                attestation_id: record.attestation_id,
+                reason,
+                revoker: revoker.clone(),
+                ledger,
+                cleared: false,
+            },
+        );
+        Self::bump_persistent(&env, &key);
+
+        // A revoked attestation no longer holds the world record
+        let record_key = DataKey::Record((system.clone(), record.verifier_hash.clone()));
+        let holds_record = env
+            .storage()
+            .persistent()
+            .get::<DataKey, RecordEntry>(&record_key)
+            .is_some_and(|r| r.attestation_id == record.attestation_id);
+        if holds_record {
+            env.storage().persistent().remove(&record_key);
+        }
+
+        env.events().publish(
+            (symbol_short!("revoked"), owner, system, tier),
+            (record.attestation_id, reason, revoker, ledger),
+        );
+        Ok(())
+    }
+
+    /// Let (owner, system, tier) be attested again. The revoked record
+    /// stays revoked until a new attestation replaces it. `revoker` must be
+    /// the admin or the system's issuer.
+    pub fn clear_revocation(
+        env: Env,
+        revoker: Address,
+        owner: Address,
+        system: Symbol,
+        tier: Symbol,
+    ) -> Result<(), RevocationError> {
+        Self::require_revoker(&env, &revoker, &system)?;
+        let key = DataKey::Revoked((owner.clone(), system.clone(), tier.clone()));
+        let mut revocation: Revocation = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .filter(|r: &Revocation| !r.cleared)
+            .ok_or(RevocationError::NotFound)?;
+        revocation.cleared = true;
+        env.storage().persistent().set(&key, &revocation);
         Self::bump_persistent(&env, &key);
+
+        env.events().publish(
+            (symbol_short!("cleared"), owner, system, tier),
+            (revocation.attestation_id, revoker),
+        );
+        Ok(())
+    }
+
+    /// The revocation of the current attestation of (owner, system, tier),
+    /// if it was revoked.
+    pub fn get_revocation(
+        env: Env,
+        owner: Address,
+        system: Symbol,
+        tier: Symbol,
+    ) -> Option<Revocation> {
+        let record: AttestationRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entry((owner.clone(), system.clone(), tier.clone())))?;
+        env.storage()
+            .persistent()
+            .get::<DataKey, Revocation>(&DataKey::Revoked((owner, system, tier)))
+            .filter(|r| r.attestation_id == record.attestation_id)
+    }
+
+    // ── Groth16 VK Registry ─────────────────────────────────────────
+
+    /// Add a new version of `vk_id` and make it the one attestations use.
+    /// Earlier versions are kept unchanged. `vk_hash` pins the upload: it
+    /// must equal sha256 of the VK's XDR. Returns the new version number.
+    pub fn register_groth16_vk(
+        env: Env,
+        vk_id: Symbol,
+        vk: Groth16VerificationKey,
+        vk_hash: BytesN<32>,
+    ) -> Result<u32, Groth16Error> {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        let hash: BytesN<32> = env.crypto().sha256(&vk.clone().to_xdr(&env)).into();
+        if hash != vk_hash {
+            return Err(Groth16Error::VkHashMismatch);
+        }
+
+        Self::migrate_vk(&env, &vk_id);
+        let version = Self::vk_count(&env, &vk_id) + 1;
+        Self::put_vk_version(
+            &env,
+            &vk_id,
+            &vk,
+            &VkVersion {
+                version,
+                hash,
+                status: VkStatus::Active,
+                registered_ledger: env.ledger().sequence(),
+            },
+        );
+        Self::bump_instance(&env);
+        Ok(version)
+    }
+
+    /// Stop accepting new attestations from `version` of `vk_id`. If it was
+    /// the newest active version, the next older active one takes over.
+    pub fn deprecate_groth16_vk(env: Env, vk_id: Symbol, version: u32) -> Result<(), Groth16Error> {
+        Self::set_vk_status(&env, &vk_id, version, VkStatus::Deprecated)
+    }
+
+    /// Delete the key of `version` of `vk_id`, keeping its metadata.
+    pub fn remove_groth16_vk(env: Env, vk_id: Symbol, version: u32) -> Result<(), Groth16Error> {
+        Self::set_vk_status(&env, &vk_id, version, VkStatus::Removed)?;
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Groth16VkData((vk_id, version)));
+        Ok(())
+    }
+
+    /// Move a VK registered before versioning into the versioned registry
+    /// as version 1, so it is listed. No-op for other ids.
+    pub fn migrate_groth16_vk(env: Env, vk_id: Symbol) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        Self::migrate_vk(&env, &vk_id);
         Self::bump_instance(&env);
     }
 
+    /// True if some version of `vk_id` is active.
     pub fn has_groth16_vk(env: Env, vk_id: Symbol) -> bool {
-        env.storage().persistent().has(&DataKey::Groth16Vk(vk_id))
+        Self::current_vk(&env, &vk_id).is_ok()
+    }
+
+    /// Every VK id in the versioned registry.
+    pub fn list_groth16_vks(env: Env) -> Vec<Symbol> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Groth16VkIds)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// All versions of `vk_id`, oldest first.
+    pub fn list_groth16_vk_versions(env: Env, vk_id: Symbol) -> Vec<VkVersion> {
+        let mut versions = Vec::new(&env);
+        for version in 1..=Self::vk_count(&env, &vk_id) {
+            versions.push_back(Self::vk_meta(&env, &vk_id, version).unwrap());
+        }
+        versions
+    }
+
+    /// The key of `version` of `vk_id`, unless it was removed.
+    pub fn get_groth16_vk(env: Env, vk_id: Symbol, version: u32) -> Option<Groth16VerificationKey> {
+        Self::vk_data(&env, &vk_id, version)
+    }
+
+    /// The VK version that verified attestation `attestation_id`, for
+    /// attestations made through `verify_groth16_and_attest`.
+    pub fn get_attestation_vk(env: Env, attestation_id: u64) -> Option<VkRef> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AttestationVk(attestation_id))
     }
 
     // ── UltraHonk Bridge ────────────────────────────────────────────
@@ -214,24 +704,82 @@ impl FarmAttestations {
         env.storage().instance().get(&DataKey::UltraHonkVerifier)
     }
 
+    // ── Attestation Bindings ────────────────────────────────────────
+
+    /// Declare how proofs verified under (`route`, `id`) become records.
+    /// `id` is the VK id for `Groth16` and `UltraHonk`, and a free label
+    /// for `UltraHonkDefault`. `binding.system` is closed to digest-only
+    /// attestation from then on.
+    pub fn register_binding(env: Env, route: ProofRoute, id: Symbol, binding: AttestationBinding) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        assert!(
+            route != ProofRoute::UltraHonkDefault || binding.vk_hash.is_some(),
+            "default vk binding needs vk_hash"
+        );
+        Self::bind_system(&env, &binding.system);
+        let key = DataKey::Binding((route, id));
+        env.storage().persistent().set(&key, &binding);
+        Self::bump_persistent(&env, &key);
+        Self::bump_instance(&env);
+    }
+
+    /// True if `system` is named by a binding or an allowlisted RISC Zero
+    /// image, so only verified routes may attest to it.
+    pub fn is_bound_system(env: Env, system: Symbol) -> bool {
+        env.storage().persistent().has(&DataKey::BoundSystem(system))
+    }
+
+    pub fn get_binding(env: Env, route: ProofRoute, id: Symbol) -> Option<AttestationBinding> {
+        env.storage().persistent().get(&DataKey::Binding((route, id)))
+    }
+
+    /// The `statement_hash` stored for a proof verified under `id`:
+    /// sha256(XDR(id) || public inputs as 32-byte big-endian fields).
+    /// Each statement attests once per system, so a proof cannot be
+    /// replayed.
+    pub fn statement_hash(env: Env, id: Symbol, public_inputs: Vec<BytesN<32>>) -> BytesN<32> {
+        let mut data = id.to_xdr(&env);
+        for input in public_inputs.iter() {
+            data.append(&input.into());
+        }
+        env.crypto().sha256(&data).into()
+    }
+
+    /// The public input value that binds a proof to `owner`:
+    /// sha256(XDR(owner)) with its top byte cleared, so it is below r.
+    pub fn owner_field(env: Env, owner: Address) -> BytesN<32> {
+        let mut field: [u8; 32] = env.crypto().sha256(&owner.to_xdr(&env)).to_array();
+        field[0] = 0;
+        BytesN::from_array(&env, &field)
+    }
+
     // ── Verified Attestation (UltraHonk) ────────────────────────────
 
+    /// Verify against the ultrahonk verifier's built-in VK and attest as
+    /// declared by the `UltraHonkDefault` binding `binding_id`. The built-in
+    /// VK must still hash to the binding's `vk_hash`.
     pub fn verify_ultrahonk_and_attest(
         env: Env,
         owner: Address,
-        system: Symbol,
-        tier: Symbol,
-        statement_hash: BytesN<32>,
-        verifier_hash: BytesN<32>,
+        binding_id: Symbol,
         public_inputs: Bytes,
         proof_bytes: Bytes,
     ) -> Result<u64, UltraHonkError> {
         owner.require_auth();
-        let verifier: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::UltraHonkVerifier)
-            .ok_or(UltraHonkError::NotConfigured)?;
+        let verifier = Self::get_ultrahonk_verifier(env.clone()).ok_or(UltraHonkError::NotConfigured)?;
+        let binding = Self::get_binding(env.clone(), ProofRoute::UltraHonkDefault, binding_id.clone())
+            .ok_or(UltraHonkError::UnknownBinding)?;
+        let inputs = Self::unpack(&env, &public_inputs).ok_or(UltraHonkError::InvalidPublicInputs)?;
+        let (tier, score) = Self::derive(&binding, &inputs).ok_or(UltraHonkError::InvalidPublicInputs)?;
+        if !Self::binds_owner(&env, &binding, &inputs, &owner) {
+            return Err(UltraHonkError::OwnerMismatch);
+        }
+        let vk_hash: Option<BytesN<32>> =
+            env.invoke_contract(&verifier, &Symbol::new(&env, "vk_hash"), Vec::new(&env));
+        if vk_hash != binding.vk_hash {
+            return Err(UltraHonkError::VkMismatch);
+        }
 
         let mut args: Vec<Val> = Vec::new(&env);
         args.push_back(public_inputs.into_val(&env));
@@ -244,29 +792,44 @@ impl FarmAttestations {
         if !ok {
             return Err(UltraHonkError::VerificationFailed);
         }
-        Ok(Self::store(&env, owner, system, tier, statement_hash, verifier_hash, 0))
+        let statement_hash = Self::statement_hash(env.clone(), binding_id, inputs);
+        if !Self::claim_statement(&env, &binding.system, &statement_hash, &owner) {
+            return Err(UltraHonkError::StatementUsed);
+        }
+        let verifier_hash = env.crypto().sha256(&verifier.to_xdr(&env)).into();
+        Ok(Self::store(&env, owner, binding.system, tier, statement_hash, verifier_hash, score)?)
     }
 
+    /// Verify against UltraHonk VK `vk_id` and attest as declared by its
+    /// `UltraHonk` binding.
     pub fn verify_ultrahonk_vk_and_attest(
         env: Env,
         owner: Address,
-        system: Symbol,
-        tier: Symbol,
-        statement_hash: BytesN<32>,
-        verifier_hash: BytesN<32>,
         vk_id: Symbol,
         public_inputs: Bytes,
         proof_bytes: Bytes,
     ) -> Result<u64, UltraHonkError> {
         owner.require_auth();
-        let verifier: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::UltraHonkVerifier)
-            .ok_or(UltraHonkError::NotConfigured)?;
+        let verifier = Self::get_ultrahonk_verifier(env.clone()).ok_or(UltraHonkError::NotConfigured)?;
+        let binding = Self::get_binding(env.clone(), ProofRoute::UltraHonk, vk_id.clone())
+            .ok_or(UltraHonkError::UnknownBinding)?;
+        let inputs = Self::unpack(&env, &public_inputs).ok_or(UltraHonkError::InvalidPublicInputs)?;
+        let (tier, score) = Self::derive(&binding, &inputs).ok_or(UltraHonkError::InvalidPublicInputs)?;
+        if !Self::binds_owner(&env, &binding, &inputs, &owner) {
+            return Err(UltraHonkError::OwnerMismatch);
+        }
+        if binding.vk_hash.is_some() {
+            let mut args: Vec<Val> = Vec::new(&env);
+            args.push_back(vk_id.clone().into_val(&env));
+            let vk_hash: Option<BytesN<32>> =
+                env.invoke_contract(&verifier, &Symbol::new(&env, "vk_hash_by_id"), args);
+            if vk_hash != binding.vk_hash {
+                return Err(UltraHonkError::VkMismatch);
+            }
+        }
 
         let mut args: Vec<Val> = Vec::new(&env);
-        args.push_back(vk_id.into_val(&env));
+        args.push_back(vk_id.clone().into_val(&env));
         args.push_back(public_inputs.into_val(&env));
         args.push_back(proof_bytes.into_val(&env));
         let ok: bool = env.invoke_contract(
@@ -277,7 +840,129 @@ impl FarmAttestations {
         if !ok {
             return Err(UltraHonkError::VerificationFailed);
         }
-        Ok(Self::store(&env, owner, system, tier, statement_hash, verifier_hash, 0))
+        let statement_hash = Self::statement_hash(env.clone(), vk_id.clone(), inputs);
+        if !Self::claim_statement(&env, &binding.system, &statement_hash, &owner) {
+            return Err(UltraHonkError::StatementUsed);
+        }
+        let verifier_hash = env.crypto().sha256(&(verifier, vk_id).to_xdr(&env)).into();
+        Ok(Self::store(&env, owner, binding.system, tier, statement_hash, verifier_hash, score)?)
+    }
+
+    // ── RISC Zero Bridge ────────────────────────────────────────────
+
+    pub fn set_risc0_verifier(env: Env, config: Risc0Verifier) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::Risc0Verifier, &config);
+        Self::bump_instance(&env);
+    }
+
+    pub fn get_risc0_verifier(env: Env) -> Option<Risc0Verifier> {
+        env.storage().instance().get(&DataKey::Risc0Verifier)
+    }
+
+    /// Accept receipts of guest `image_id`, attesting under `image.system`.
+    /// `image.min_thresholds` holds one minimum per entry of `image.tiers`.
+    /// `image.system` is closed to digest-only attestation from then on.
+    pub fn allow_risc0_image(env: Env, image_id: BytesN<32>, image: Risc0Image) -> Result<(), Risc0Error> {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        if image.min_thresholds.len() != image.tiers.len() {
+            return Err(Risc0Error::ThresholdsMismatch);
+        }
+        Self::bind_system(&env, &image.system);
+        let key = DataKey::Risc0Image(image_id);
+        env.storage().persistent().set(&key, &image);
+        Self::bump_persistent(&env, &key);
+        Self::bump_instance(&env);
+        Ok(())
+    }
+
+    pub fn remove_risc0_image(env: Env, image_id: BytesN<32>) {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        env.storage().persistent().remove(&DataKey::Risc0Image(image_id));
+        Self::bump_instance(&env);
+    }
+
+    pub fn get_risc0_image(env: Env, image_id: BytesN<32>) -> Option<Risc0Image> {
+        env.storage().persistent().get(&DataKey::Risc0Image(image_id))
+    }
+
+    /// The owner who attested with risc0-tier balance commitment
+    /// `commitment`, if any.
+    pub fn get_risc0_commitment(env: Env, commitment: BytesN<32>) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Risc0Commitment(commitment))
+    }
+
+    // ── Verified Attestation (RISC Zero) ────────────────────────────
+
+    /// Verify a Groth16 receipt of the allowlisted risc0-tier guest
+    /// `image_id` whose journal is `journal`, and attest from the journal:
+    /// `tier_index` selects the tier, `threshold` becomes the score and the
+    /// balance commitment the `statement_hash`. `verifier_hash` is the
+    /// image ID.
+    ///
+    /// The guest does not tie `tier_index` to `threshold`, so a tier is only
+    /// granted when `threshold` reaches its minimum. The journal names the
+    /// owner as `owner_field(owner)`, so a receipt seen in the mempool
+    /// cannot be submitted for another address, and each commitment
+    /// attests once.
+    pub fn verify_risc0_and_attest(
+        env: Env,
+        owner: Address,
+        image_id: BytesN<32>,
+        journal: Bytes,
+        seal: Groth16Proof,
+    ) -> Result<u64, Risc0Error> {
+        owner.require_auth();
+        let config = Self::get_risc0_verifier(env.clone()).ok_or(Risc0Error::NotConfigured)?;
+        let image = Self::get_risc0_image(env.clone(), image_id.clone()).ok_or(Risc0Error::ImageNotAllowed)?;
+        let (tier_index, threshold, commitment, owner_field) =
+            Self::decode_tier_journal(&env, &journal).ok_or(Risc0Error::InvalidJournal)?;
+        if owner_field != Self::owner_field(env.clone(), owner.clone()) {
+            return Err(Risc0Error::OwnerMismatch);
+        }
+        let tier = image.tiers.get(tier_index).ok_or(Risc0Error::InvalidJournal)?;
+        let min_threshold = image.min_thresholds.get(tier_index).ok_or(Risc0Error::InvalidJournal)?;
+        if threshold < min_threshold {
+            return Err(Risc0Error::ThresholdTooLow);
+        }
+        let commitment_key = DataKey::Risc0Commitment(commitment.clone());
+        if env.storage().persistent().has(&commitment_key) {
+            return Err(Risc0Error::CommitmentUsed);
+        }
+
+        let claim = risc0_claim::claim_digest(&env, &image_id, &journal);
+        let (claim_0, claim_1) = risc0_claim::split_digest(&env, &claim);
+        let mut receipt_inputs: Vec<BytesN<32>> = Vec::new(&env);
+        receipt_inputs.push_back(config.control_root_0);
+        receipt_inputs.push_back(config.control_root_1);
+        receipt_inputs.push_back(claim_0);
+        receipt_inputs.push_back(claim_1);
+        receipt_inputs.push_back(config.bn254_control_id);
+
+        // The receipt verifier records an attestation for its `owner`
+        // argument; this contract passes itself, which needs no signature.
+        let mut args: Vec<Val> = Vec::new(&env);
+        args.push_back(env.current_contract_address().into_val(&env));
+        args.push_back(claim.into_val(&env));
+        args.push_back(receipt_inputs.into_val(&env));
+        args.push_back(seal.into_val(&env));
+        let ok = env
+            .try_invoke_contract::<bool, soroban_sdk::Error>(
+                &config.verifier,
+                &Symbol::new(&env, "verify_and_attest"),
+                args,
+            )
+            .map_err(|_| Risc0Error::VerificationFailed)?
+            .map_err(|_| Risc0Error::VerificationFailed)?;
+        if !ok {
+            return Err(Risc0Error::VerificationFailed);
+        }
+        env.storage().persistent().set(&commitment_key, &owner);
+        Self::bump_persistent(&env, &commitment_key);
+        Ok(Self::store(&env, owner, image.system, tier, commitment, image_id, threshold)?)
     }
 
     // ── Verified Attestation (Groth16) ──────────────────────────────
@@ -288,39 +973,52 @@ impl FarmAttestations {
         public_inputs: Vec<BytesN<32>>,
         proof: Groth16Proof,
     ) -> Result<bool, Groth16Error> {
-        let vk: Groth16VerificationKey = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Groth16Vk(vk_id))
-            .ok_or(Groth16Error::UnknownVk)?;
+        let (_, vk) = Self::current_vk(&env, &vk_id)?;
         Self::groth16_verify(&env, &vk, &public_inputs, &proof)?;
         Ok(true)
     }
 
+    /// Verify against the newest active version of registry VK `vk_id` and
+    /// attest as declared by its `Groth16` binding.
+    /// `verifier_hash` is the version's hash; `get_attestation_vk` tells
+    /// which version it was.
     pub fn verify_groth16_and_attest(
         env: Env,
         owner: Address,
-        system: Symbol,
-        tier: Symbol,
-        statement_hash: BytesN<32>,
-        verifier_hash: BytesN<32>,
         vk_id: Symbol,
         public_inputs: Vec<BytesN<32>>,
         proof: Groth16Proof,
     ) -> Result<u64, Groth16Error> {
         owner.require_auth();
-        let vk: Groth16VerificationKey = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Groth16Vk(vk_id))
-            .ok_or(Groth16Error::UnknownVk)?;
+        let (meta, vk) = Self::current_vk(&env, &vk_id)?;
+        let binding = Self::get_binding(env.clone(), ProofRoute::Groth16, vk_id.clone())
+            .ok_or(Groth16Error::UnknownBinding)?;
+        let (tier, score) = Self::derive(&binding, &public_inputs).ok_or(Groth16Error::InvalidPublicInputs)?;
+        if !Self::binds_owner(&env, &binding, &public_inputs, &owner) {
+            return Err(Groth16Error::OwnerMismatch);
+        }
+        if binding.vk_hash.as_ref().is_some_and(|h| *h != meta.hash) {
+            return Err(Groth16Error::VkMismatch);
+        }
         Self::groth16_verify(&env, &vk, &public_inputs, &proof)?;
-        Ok(Self::store(&env, owner, system, tier, statement_hash, verifier_hash, 0))
+
+        let statement_hash = Self::statement_hash(env.clone(), vk_id.clone(), public_inputs);
+        if !Self::claim_statement(&env, &binding.system, &statement_hash, &owner) {
+            return Err(Groth16Error::StatementUsed);
+        }
+        let attestation_id = Self::store(&env, owner, binding.system, tier, statement_hash, meta.hash, score)?;
+
+        let key = DataKey::AttestationVk(attestation_id);
+        env.storage().persistent().set(&key, &VkRef { vk_id, version: meta.version });
+        Self::bump_persistent(&env, &key);
+        Ok(attestation_id)
     }
 
     // ── Core Attestation ────────────────────────────────────────────
 
-    /// Digest-only attestation (no on-chain proof verification).
+    /// Digest-only attestation (no on-chain proof verification). Systems
+    /// with a binding or an allowlisted RISC Zero image are refused with
+    /// `SystemBound`, here and in every digest-only variant below.
     pub fn attest(
         env: Env,
         owner: Address,
@@ -328,8 +1026,9 @@ impl FarmAttestations {
         tier: Symbol,
         statement_hash: BytesN<32>,
         verifier_hash: BytesN<32>,
-    ) -> u64 {
+    ) -> Result<u64, AttestError> {
         owner.require_auth();
+        Self::require_unbound(&env, &system)?;
         Self::store(&env, owner, system, tier, statement_hash, verifier_hash, 0)
     }
 
@@ -342,8 +1041,9 @@ impl FarmAttestations {
         statement_hash: BytesN<32>,
         verifier_hash: BytesN<32>,
         score: u64,
-    ) -> u64 {
+    ) -> Result<u64, AttestError> {
         owner.require_auth();
+        Self::require_unbound(&env, &system)?;
         Self::store(&env, owner, system, tier, statement_hash, verifier_hash, score)
     }
 
@@ -358,12 +1058,12 @@ impl FarmAttestations {
         statement_hash: BytesN<32>,
         verifier_hash: BytesN<32>,
         score: u64,
-    ) -> u64 {
+    ) -> Result<u64, AttestError> {
         owner.require_auth();
-        let key = DataKey::Entry((owner.clone(), system.clone(), tier.clone()));
-        if let Some(existing) = env.storage().persistent().get::<DataKey, AttestationRecord>(&key) {
+        Self::require_unbound(&env, &system)?;
+        if let Some(existing) = Self::get(env.clone(), owner.clone(), system.clone(), tier.clone()) {
             if existing.score >= score {
-                return 0;
+                return Ok(0);
             }
         }
         Self::store(&env, owner, system, tier, statement_hash, verifier_hash, score)
@@ -378,7 +1078,7 @@ impl FarmAttestations {
         statement_hashes: Vec<BytesN<32>>,
         verifier_hashes: Vec<BytesN<32>>,
         scores: Vec<u64>,
-    ) -> u64 {
+    ) -> Result<u64, AttestError> {
         owner.require_auth();
         let n = systems.len();
         assert!(n > 0, "empty batch");
@@ -391,25 +1091,34 @@ impl FarmAttestations {
         );
         let mut last = 0u64;
         for i in 0..n {
+            let system = systems.get(i).unwrap();
+            Self::require_unbound(&env, &system)?;
             last = Self::store(
                 &env,
                 owner.clone(),
-                systems.get(i).unwrap(),
+                system,
                 tiers.get(i).unwrap(),
                 statement_hashes.get(i).unwrap(),
                 verifier_hashes.get(i).unwrap(),
                 scores.get(i).unwrap(),
-            );
+            )?;
         }
-        last
+        Ok(last)
     }
 
     // ── Reads ───────────────────────────────────────────────────────
 
+    /// The attestation of (owner, system, tier), unless it has expired or
+    /// been revoked.
     pub fn get(env: Env, owner: Address, system: Symbol, tier: Symbol) -> Option<AttestationRecord> {
-        env.storage()
+        let record: AttestationRecord = env
+            .storage()
             .persistent()
-            .get(&DataKey::Entry((owner, system, tier)))
+            .get(&DataKey::Entry((owner, system, tier)))?;
+        if Self::is_revoked(&env, &record) || Self::is_expired(&env, &record) {
+            return None;
+        }
+        Some(record)
     }
 
     pub fn has(
@@ -419,9 +1128,7 @@ impl FarmAttestations {
         tier: Symbol,
         statement_hash: BytesN<32>,
     ) -> bool {
-        match env.storage().persistent().get::<DataKey, AttestationRecord>(
-            &DataKey::Entry((owner, system, tier)),
-        ) {
+        match Self::get(env, owner, system, tier) {
             Some(r) => r.statement_hash == statement_hash,
             None => false,
         }
@@ -431,17 +1138,40 @@ impl FarmAttestations {
 
     /// Every system+tier an owner has attested to, collected like badges.
     pub fn get_passport(env: Env, owner: Address) -> Vec<Badge> {
-        env.storage()
-            .persistent()
-            .get(&DataKey::OwnerIndex(owner))
-            .unwrap_or_else(|| Vec::new(&env))
+        let len = Self::count_by_owner(env.clone(), owner.clone());
+        Self::idx_range(
+            &env,
+            &DataKey::OwnerLen(owner.clone()),
+            &DataKey::OwnerIndex(owner.clone()),
+            |page| DataKey::OwnerPage((owner.clone(), page)),
+            0,
+            len,
+        )
+    }
+
+    /// Up to `limit` badges (at most `INDEX_PAGE_SIZE`) starting at
+    /// position `cursor`. A short page means the end was reached.
+    pub fn get_passport_page(env: Env, owner: Address, cursor: u32, limit: u32) -> Vec<Badge> {
+        Self::idx_range(
+            &env,
+            &DataKey::OwnerLen(owner.clone()),
+            &DataKey::OwnerIndex(owner.clone()),
+            |page| DataKey::OwnerPage((owner.clone(), page)),
+            cursor,
+            limit.min(INDEX_PAGE_SIZE),
+        )
+    }
+
+    pub fn count_by_owner(env: Env, owner: Address) -> u32 {
+        Self::idx_len(&env, &DataKey::OwnerLen(owner.clone()), &DataKey::OwnerIndex(owner)).total
     }
 
     // ── #3  Gate Check (Composable Access) ──────────────────────────
 
-    /// Returns true only if the owner holds attestations for every
-    /// (system, tier) pair in the provided lists. Useful as a
-    /// prerequisite check before granting access to gated content.
+    /// Returns true only if the owner holds live (unexpired, unrevoked)
+    /// attestations for every (system, tier) pair in the provided lists.
+    /// Useful as a prerequisite check before granting access to gated
+    /// content.
     pub fn check_access(
         env: Env,
         owner: Address,
@@ -451,12 +1181,13 @@ impl FarmAttestations {
         let n = systems.len();
         assert!(n == tiers.len(), "mismatched lengths");
         for i in 0..n {
-            let key = DataKey::Entry((
+            let record = Self::get(
+                env.clone(),
                 owner.clone(),
                 systems.get(i).unwrap(),
                 tiers.get(i).unwrap(),
-            ));
-            if !env.storage().persistent().has(&key) {
+            );
+            if record.is_none() {
                 return false;
             }
         }
@@ -467,11 +1198,7 @@ impl FarmAttestations {
 
     /// Aggregate stats derived from the owner's badge passport.
     pub fn get_stats(env: Env, owner: Address) -> OwnerStats {
-        let badges: Vec<Badge> = env
-            .storage()
-            .persistent()
-            .get(&DataKey::OwnerIndex(owner))
-            .unwrap_or_else(|| Vec::new(&env));
+        let badges = Self::get_passport(env.clone(), owner);
 
         let total_badges = badges.len();
 
@@ -510,20 +1237,35 @@ impl FarmAttestations {
     // ── System Index (Leaderboard Enumeration) ──────────────────────
 
     /// All (owner, tier) pairs that hold attestations under a system.
+    /// Reads every page; use `list_by_system_page` for large systems.
     pub fn list_by_system(env: Env, system: Symbol) -> Vec<IndexEntry> {
-        env.storage()
-            .persistent()
-            .get(&DataKey::SystemIndex(system))
-            .unwrap_or_else(|| Vec::new(&env))
+        let len = Self::count_by_system(env.clone(), system.clone());
+        Self::idx_range(
+            &env,
+            &DataKey::SystemLen(system.clone()),
+            &DataKey::SystemIndex(system.clone()),
+            |page| DataKey::SystemPage((system.clone(), page)),
+            0,
+            len,
+        )
+    }
+
+    /// Up to `limit` (owner, tier) pairs (at most `INDEX_PAGE_SIZE`)
+    /// starting at position `cursor`, in attestation order. A short page
+    /// means the end was reached.
+    pub fn list_by_system_page(env: Env, system: Symbol, cursor: u32, limit: u32) -> Vec<IndexEntry> {
+        Self::idx_range(
+            &env,
+            &DataKey::SystemLen(system.clone()),
+            &DataKey::SystemIndex(system.clone()),
+            |page| DataKey::SystemPage((system.clone(), page)),
+            cursor,
+            limit.min(INDEX_PAGE_SIZE),
+        )
     }
 
     pub fn count_by_system(env: Env, system: Symbol) -> u32 {
-        let idx: Vec<IndexEntry> = env
-            .storage()
-            .persistent()
-            .get(&DataKey::SystemIndex(system))
-            .unwrap_or_else(|| Vec::new(&env));
-        idx.len()
+        Self::idx_len(&env, &DataKey::SystemLen(system.clone()), &DataKey::SystemIndex(system)).total
     }
 
     // ════════════════════════════════════════════════════════════════
@@ -539,7 +1281,14 @@ impl FarmAttestations {
         statement_hash: BytesN<32>,
         verifier_hash: BytesN<32>,
         score: u64,
-    ) -> u64 {
+    ) -> Result<u64, AttestError> {
+        let revoked_key = DataKey::Revoked((owner.clone(), system.clone(), tier.clone()));
+        if let Some(revocation) = env.storage().persistent().get::<DataKey, Revocation>(&revoked_key) {
+            if !revocation.cleared {
+                return Err(AttestError::Revoked);
+            }
+        }
+
         let attestation_id = Self::next_nonce(env);
         let ledger = env.ledger().sequence();
         let timestamp = env.ledger().timestamp();
@@ -558,12 +1307,37 @@ impl FarmAttestations {
 
         // Write the entry
         let entry_key = DataKey::Entry((owner.clone(), system.clone(), tier.clone()));
+        let is_new = !env.storage().persistent().has(&entry_key);
         env.storage().persistent().set(&entry_key, &record);
         Self::bump_persistent(env, &entry_key);
 
-        // Update indexes
-        Self::idx_system(env, &system, &owner, &tier);
-        Self::idx_owner(env, &owner, &system, &tier);
+        // Index each (owner, system, tier) once. Entries written before v3
+        // were never indexed: an existing entry without the v4 marker is
+        // indexed unless the frozen v3 index already lists it.
+        let indexed_key = DataKey::Indexed((owner.clone(), system.clone(), tier.clone()));
+        let needs_index = is_new
+            || (!env.storage().persistent().has(&indexed_key)
+                && !Self::in_legacy_index(env, &owner, &system, &tier));
+        if needs_index {
+            Self::idx_append(
+                env,
+                &DataKey::SystemLen(system.clone()),
+                &DataKey::SystemIndex(system.clone()),
+                DataKey::SystemPage,
+                system.clone(),
+                IndexEntry { owner: owner.clone(), tier: tier.clone() },
+            );
+            Self::idx_append(
+                env,
+                &DataKey::OwnerLen(owner.clone()),
+                &DataKey::OwnerIndex(owner.clone()),
+                DataKey::OwnerPage,
+                owner.clone(),
+                Badge { system: system.clone(), tier: tier.clone() },
+            );
+        }
+        env.storage().persistent().set(&indexed_key, &true);
+        Self::bump_persistent(env, &indexed_key);
 
         // Update global record if this score is the new best
         if score > 0 {
@@ -578,61 +1352,93 @@ impl FarmAttestations {
             (statement_hash, verifier_hash, attestation_id, score, ledger, timestamp),
         );
 
-        attestation_id
+        Ok(attestation_id)
     }
 
-    /// Append to per-system index (deduped).
-    fn idx_system(env: &Env, system: &Symbol, owner: &Address, tier: &Symbol) {
-        let key = DataKey::SystemIndex(system.clone());
-        let mut idx: Vec<IndexEntry> = env
-            .storage()
+    /// True if the v3 owner index lists (system, tier) for `owner`. Only
+    /// consulted once per pre-v4 entry.
+    fn in_legacy_index(env: &Env, owner: &Address, system: &Symbol, tier: &Symbol) -> bool {
+        env.storage()
             .persistent()
-            .get(&key)
-            .unwrap_or_else(|| Vec::new(env));
+            .get::<DataKey, Vec<Badge>>(&DataKey::OwnerIndex(owner.clone()))
+            .is_some_and(|badges| badges.iter().any(|b| b.system == *system && b.tier == *tier))
+    }
 
-        let mut found = false;
-        for i in 0..idx.len() {
-            let e = idx.get(i).unwrap();
-            if e.owner == *owner && e.tier == *tier {
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            idx.push_back(IndexEntry {
-                owner: owner.clone(),
-                tier: tier.clone(),
-            });
-            env.storage().persistent().set(&key, &idx);
-            Self::bump_persistent(env, &key);
-        }
+    /// Length of a paged index; on first use, the size of its v3 index.
+    fn idx_len(env: &Env, len_key: &DataKey, legacy_key: &DataKey) -> IndexLen {
+        env.storage().persistent().get(len_key).unwrap_or_else(|| {
+            let legacy = env
+                .storage()
+                .persistent()
+                .get::<DataKey, Vec<Val>>(legacy_key)
+                .map(|v| v.len())
+                .unwrap_or(0);
+            IndexLen { legacy, total: legacy }
+        })
     }
 
-    /// Append to per-owner badge passport (deduped).
-    fn idx_owner(env: &Env, owner: &Address, system: &Symbol, tier: &Symbol) {
-        let key = DataKey::OwnerIndex(owner.clone());
-        let mut badges: Vec<Badge> = env
+    /// Append `item` to the last page of a paged index. O(1) in the index
+    /// size: callers dedupe before appending.
+    fn idx_append<K: Clone, T: IntoVal<Env, Val> + TryFromVal<Env, Val>>(
+        env: &Env,
+        len_key: &DataKey,
+        legacy_key: &DataKey,
+        page_key: fn((K, u32)) -> DataKey,
+        id: K,
+        item: T,
+    ) {
+        let mut len = Self::idx_len(env, len_key, legacy_key);
+        let key = page_key((id, (len.total - len.legacy) / INDEX_PAGE_SIZE));
+        let mut page: Vec<T> = env
             .storage()
             .persistent()
             .get(&key)
             .unwrap_or_else(|| Vec::new(env));
+        page.push_back(item);
+        env.storage().persistent().set(&key, &page);
+        Self::bump_persistent(env, &key);
 
-        let mut found = false;
-        for i in 0..badges.len() {
-            let b = badges.get(i).unwrap();
-            if b.system == *system && b.tier == *tier {
-                found = true;
-                break;
+        len.total += 1;
+        env.storage().persistent().set(len_key, &len);
+        Self::bump_persistent(env, len_key);
+    }
+
+    /// Up to `limit` entries of a paged index from position `cursor`.
+    fn idx_range<T: IntoVal<Env, Val> + TryFromVal<Env, Val>>(
+        env: &Env,
+        len_key: &DataKey,
+        legacy_key: &DataKey,
+        page_key: impl Fn(u32) -> DataKey,
+        cursor: u32,
+        limit: u32,
+    ) -> Vec<T> {
+        let len = Self::idx_len(env, len_key, legacy_key);
+        let end = len.total.min(cursor.saturating_add(limit));
+        let mut out: Vec<T> = Vec::new(env);
+        let mut pos = cursor;
+
+        if pos < len.legacy && pos < end {
+            let legacy: Vec<T> = env.storage().persistent().get(legacy_key).unwrap();
+            while pos < len.legacy && pos < end {
+                out.push_back(legacy.get(pos).unwrap());
+                pos += 1;
             }
         }
-        if !found {
-            badges.push_back(Badge {
-                system: system.clone(),
-                tier: tier.clone(),
-            });
-            env.storage().persistent().set(&key, &badges);
-            Self::bump_persistent(env, &key);
+        while pos < end {
+            let offset = pos - len.legacy;
+            let page: Vec<T> = env
+                .storage()
+                .persistent()
+                .get(&page_key(offset / INDEX_PAGE_SIZE))
+                .unwrap();
+            let mut i = offset % INDEX_PAGE_SIZE;
+            while i < page.len() && pos < end {
+                out.push_back(page.get(i).unwrap());
+                i += 1;
+                pos += 1;
+            }
         }
+        out
     }
 
     /// Update global record if this score beats the current best.
@@ -667,6 +1473,103 @@ impl FarmAttestations {
         }
     }
 
+    /// Tier and score a binding reads from `public_inputs`, or None if an
+    /// input is missing or out of range.
+    fn derive(binding: &AttestationBinding, public_inputs: &Vec<BytesN<32>>) -> Option<(Symbol, u64)> {
+        let tier_index = Self::input_as_u64(&public_inputs.get(binding.tier_input)?)?;
+        let tier = binding.tiers.get(u32::try_from(tier_index).ok()?)?;
+        let score = match binding.score_input {
+            Some(i) => Self::input_as_u64(&public_inputs.get(i)?)?,
+            None => 0,
+        };
+        Some((tier, score))
+    }
+
+    /// True if the binding's owner input is `owner_field(owner)`.
+    fn binds_owner(env: &Env, binding: &AttestationBinding, public_inputs: &Vec<BytesN<32>>, owner: &Address) -> bool {
+        public_inputs.get(binding.owner_input) == Some(Self::owner_field(env.clone(), owner.clone()))
+    }
+
+    /// A 32-byte big-endian field element as u64, if it fits.
+    fn input_as_u64(input: &BytesN<32>) -> Option<u64> {
+        let bytes = input.to_array();
+        if bytes[..24].iter().any(|b| *b != 0) {
+            return None;
+        }
+        let mut low = [0u8; 8];
+        low.copy_from_slice(&bytes[24..]);
+        Some(u64::from_be_bytes(low))
+    }
+
+    /// Split packed public inputs into 32-byte fields, each below r.
+    fn unpack(env: &Env, packed: &Bytes) -> Option<Vec<BytesN<32>>> {
+        if !packed.len().is_multiple_of(32) {
+            return None;
+        }
+        let mut inputs = Vec::new(env);
+        for i in 0..packed.len() / 32 {
+            let input: BytesN<32> = packed.slice(i * 32..(i + 1) * 32).try_into().ok()?;
+            if !Self::is_field_element(&input) {
+                return None;
+            }
+            inputs.push_back(input);
+        }
+        Some(inputs)
+    }
+
+    /// True if a 32-byte big-endian input is a canonical BN254 scalar.
+    fn is_field_element(input: &BytesN<32>) -> bool {
+        input.to_array() < BN254_SCALAR_MODULUS
+    }
+
+    /// Record `statement_hash` as attested in `system` for `owner`; false
+    /// if it already was.
+    fn claim_statement(env: &Env, system: &Symbol, statement_hash: &BytesN<32>, owner: &Address) -> bool {
+        let key = DataKey::Statement((system.clone(), statement_hash.clone()));
+        if env.storage().persistent().has(&key) {
+            return false;
+        }
+        env.storage().persistent().set(&key, owner);
+        Self::bump_persistent(env, &key);
+        true
+    }
+
+    fn bind_system(env: &Env, system: &Symbol) {
+        let key = DataKey::BoundSystem(system.clone());
+        env.storage().persistent().set(&key, &true);
+        Self::bump_persistent(env, &key);
+    }
+
+    fn require_unbound(env: &Env, system: &Symbol) -> Result<(), AttestError> {
+        if Self::is_bound_system(env.clone(), system.clone()) {
+            return Err(AttestError::SystemBound);
+        }
+        Ok(())
+    }
+
+    fn require_revoker(env: &Env, revoker: &Address, system: &Symbol) -> Result<(), RevocationError> {
+        revoker.require_auth();
+        if *revoker != Self::admin(env.clone())
+            && Some(revoker.clone()) != Self::get_issuer(env.clone(), system.clone())
+        {
+            return Err(RevocationError::NotAuthorized);
+        }
+        Ok(())
+    }
+
+    fn is_revoked(env: &Env, record: &AttestationRecord) -> bool {
+        let key = DataKey::Revoked((record.owner.clone(), record.system.clone(), record.tier.clone()));
+        match env.storage().persistent().get::<DataKey, Revocation>(&key) {
+            Some(r) => r.attestation_id == record.attestation_id,
+            None => false,
+        }
+    }
+
+    fn is_expired(env: &Env, record: &AttestationRecord) -> bool {
+        let validity = Self::get_validity(env.clone(), record.system.clone());
+        validity > 0 && env.ledger().sequence() > record.ledger.saturating_add(validity)
+    }
+
     fn next_nonce(env: &Env) -> u64 {
         let n = env
             .storage()
@@ -692,6 +1595,158 @@ impl FarmAttestations {
             .extend_ttl(key, if max > 1 { max / 2 } else { 1 }, max);
     }
 
+    // ── Groth16 VK Versions ─────────────────────────────────────────
+
+    /// Number of versions of `vk_id`; a pre-versioning key counts as 1.
+    fn vk_count(env: &Env, vk_id: &Symbol) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Groth16VkCount(vk_id.clone()))
+            .unwrap_or_else(|| {
+                if env.storage().persistent().has(&DataKey::Groth16Vk(vk_id.clone())) {
+                    1
+                } else {
+                    0
+                }
+            })
+    }
+
+    fn vk_data(env: &Env, vk_id: &Symbol, version: u32) -> Option<Groth16VerificationKey> {
+        let versioned = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Groth16VkData((vk_id.clone(), version)));
+        if versioned.is_some() || version != 1 {
+            return versioned;
+        }
+        env.storage().persistent().get(&DataKey::Groth16Vk(vk_id.clone()))
+    }
+
+    fn vk_meta(env: &Env, vk_id: &Symbol, version: u32) -> Option<VkVersion> {
+        let meta = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Groth16VkMeta((vk_id.clone(), version)));
+        if meta.is_some() || version != 1 {
+            return meta;
+        }
+        let legacy: Groth16VerificationKey =
+            env.storage().persistent().get(&DataKey::Groth16Vk(vk_id.clone()))?;
+        Some(VkVersion {
+            version: 1,
+            hash: env.crypto().sha256(&legacy.to_xdr(env)).into(),
+            status: VkStatus::Active,
+            registered_ledger: 0,
+        })
+    }
+
+    /// The newest active version of `vk_id` and its key.
+    fn current_vk(env: &Env, vk_id: &Symbol) -> Result<(VkVersion, Groth16VerificationKey), Groth16Error> {
+        let count = Self::vk_count(env, vk_id);
+        if count == 0 {
+            return Err(Groth16Error::UnknownVk);
+        }
+        for version in (1..=count).rev() {
+            let meta = Self::vk_meta(env, vk_id, version).ok_or(Groth16Error::UnknownVk)?;
+            if meta.status == VkStatus::Active {
+                let vk = Self::vk_data(env, vk_id, version).ok_or(Groth16Error::UnknownVk)?;
+                return Ok((meta, vk));
+            }
+        }
+        Err(Groth16Error::InactiveVk)
+    }
+
+    fn put_vk_version(env: &Env, vk_id: &Symbol, vk: &Groth16VerificationKey, meta: &VkVersion) {
+        let data_key = DataKey::Groth16VkData((vk_id.clone(), meta.version));
+        env.storage().persistent().set(&data_key, vk);
+        Self::bump_persistent(env, &data_key);
+        let meta_key = DataKey::Groth16VkMeta((vk_id.clone(), meta.version));
+        env.storage().persistent().set(&meta_key, meta);
+        Self::bump_persistent(env, &meta_key);
+        let count_key = DataKey::Groth16VkCount(vk_id.clone());
+        env.storage().persistent().set(&count_key, &meta.version);
+        Self::bump_persistent(env, &count_key);
+
+        if meta.version == 1 {
+            let mut ids = Self::list_groth16_vks(env.clone());
+            ids.push_back(vk_id.clone());
+            env.storage().persistent().set(&DataKey::Groth16VkIds, &ids);
+            Self::bump_persistent(env, &DataKey::Groth16VkIds);
+        }
+
+        env.events().publish(
+            (symbol_short!("vk"), vk_id.clone()),
+            (meta.version, meta.hash.clone(), meta.status),
+        );
+    }
+
+    /// Move a pre-versioning key of `vk_id` to version 1.
+    fn migrate_vk(env: &Env, vk_id: &Symbol) {
+        let legacy_key = DataKey::Groth16Vk(vk_id.clone());
+        if env.storage().persistent().has(&DataKey::Groth16VkCount(vk_id.clone()))
+            || !env.storage().persistent().has(&legacy_key)
+        {
+            return;
+        }
+        let vk = Self::vk_data(env, vk_id, 1).unwrap();
+        let meta = Self::vk_meta(env, vk_id, 1).unwrap();
+        env.storage().persistent().remove(&legacy_key);
+        Self::put_vk_version(env, vk_id, &vk, &meta);
+    }
+
+    fn set_vk_status(env: &Env, vk_id: &Symbol, version: u32, status: VkStatus) -> Result<(), Groth16Error> {
+        let admin = Self::admin(env.clone());
+        admin.require_auth();
+        Self::migrate_vk(env, vk_id);
+        let key = DataKey::Groth16VkMeta((vk_id.clone(), version));
+        let mut meta: VkVersion = env.storage().persistent().get(&key).ok_or(Groth16Error::UnknownVk)?;
+        let forward = meta.status == VkStatus::Active
+            || (meta.status == VkStatus::Deprecated && status == VkStatus::Removed);
+        if !forward {
+            return Err(Groth16Error::InvalidVkStatus);
+        }
+        meta.status = status;
+        env.storage().persistent().set(&key, &meta);
+        Self::bump_persistent(env, &key);
+        Self::bump_instance(env);
+
+        env.events().publish(
+            (symbol_short!("vk"), vk_id.clone()),
+            (version, meta.hash, status),
+        );
+        Ok(())
+    }
+
+    // ── RISC Zero Journal ───────────────────────────────────────────
+
+    /// Decode `(tier_index: u8, threshold: u64, commitment: [u8; 32],
+    /// owner_field: [u8; 32])` from its RISC Zero serde encoding
+    /// (little-endian u32 words).
+    fn decode_tier_journal(env: &Env, journal: &Bytes) -> Option<(u32, u64, BytesN<32>, BytesN<32>)> {
+        if journal.len() != RISC0_TIER_JOURNAL_LEN {
+            return None;
+        }
+        let word = |i: u32| -> u32 {
+            let mut w = [0u8; 4];
+            journal.slice(i * 4..i * 4 + 4).copy_into_slice(&mut w);
+            u32::from_le_bytes(w)
+        };
+        let tier_index = word(0);
+        if tier_index > u8::MAX as u32 {
+            return None;
+        }
+        let threshold = word(1) as u64 | (word(2) as u64) << 32;
+        // A [u8; 32] is one word per byte, starting at word `first`
+        let bytes32 = |first: u32| -> Option<BytesN<32>> {
+            let mut out = [0u8; 32];
+            for (i, byte) in out.iter_mut().enumerate() {
+                *byte = u8::try_from(word(first + i as u32)).ok()?;
+            }
+            Some(BytesN::from_array(env, &out))
+        };
+        Some((tier_index, threshold, bytes32(3)?, bytes32(35)?))
+    }
+
     // ── BN254 Groth16 Verification ──────────────────────────────────
 
     fn groth16_verify(
@@ -700,7 +1755,9 @@ impl FarmAttestations {
         public_inputs: &Vec<BytesN<32>>,
         proof: &Groth16Proof,
     ) -> Result<(), Groth16Error> {
-        if vk.ic.len() != public_inputs.len() + 1 {
+        if vk.ic.len() != public_inputs.len() + 1
+            || !public_inputs.iter().all(|input| Self::is_field_element(&input))
+        {
             return Err(Groth16Error::InvalidPublicInputs);
         }
 
@@ -755,3 +1812,842 @@ impl FarmAttestations {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{storage::Persistent as _, Address as _, Ledger as _};
+
+    fn setup(env: &Env) -> (Address, FarmAttestationsClient<'_>) {
+        env.mock_all_auths();
+        let id = env.register(FarmAttestations, ());
+        let client = FarmAttestationsClient::new(env, &id);
+        client.init_admin(&Address::generate(env));
+        (id, client)
+    }
+
+    fn digest(env: &Env, seed: u8) -> BytesN<32> {
+        BytesN::from_array(env, &[seed; 32])
+    }
+
+    fn attest(env: &Env, client: &FarmAttestationsClient, owner: &Address, system: &Symbol, tier: &Symbol) -> u64 {
+        client.attest(owner, system, tier, &digest(env, 1), &digest(env, 2))
+    }
+
+    fn record(env: &Env, owner: &Address, system: &Symbol, tier: &Symbol) -> AttestationRecord {
+        AttestationRecord {
+            owner: owner.clone(),
+            system: system.clone(),
+            tier: tier.clone(),
+            statement_hash: digest(env, 1),
+            verifier_hash: digest(env, 2),
+            ledger: 0,
+            timestamp: 0,
+            attestation_id: 0,
+            score: 0,
+        }
+    }
+
+    fn owners(env: &Env, entries: &Vec<IndexEntry>) -> Vec<Address> {
+        let mut out = Vec::new(env);
+        for entry in entries.iter() {
+            out.push_back(entry.owner);
+        }
+        out
+    }
+
+    fn generate(env: &Env, n: u32) -> Vec<Address> {
+        let mut out = Vec::new(env);
+        for _ in 0..n {
+            out.push_back(Address::generate(env));
+        }
+        out
+    }
+
+    /// risc0 serde encoding of `(tier_index, threshold, commitment, owner_field)`.
+    fn tier_journal(env: &Env, tier_index: u32, threshold: u64, commitment: [u8; 32], owner_field: [u8; 32]) -> Bytes {
+        let mut journal = Bytes::new(env);
+        journal.extend_from_array(&tier_index.to_le_bytes());
+        journal.extend_from_array(&(threshold as u32).to_le_bytes());
+        journal.extend_from_array(&((threshold >> 32) as u32).to_le_bytes());
+        for byte in commitment.into_iter().chain(owner_field) {
+            journal.extend_from_array(&(byte as u32).to_le_bytes());
+        }
+        journal
+    }
+
+    /// Stands in for `risc0-groth16-verifier`, accepting every receipt.
+    #[contract]
+    struct AcceptingReceiptVerifier;
+
+    #[contractimpl]
+    impl AcceptingReceiptVerifier {
+        pub fn verify_and_attest(
+            _env: Env,
+            _owner: Address,
+            _claim: BytesN<32>,
+            _public_inputs: Vec<BytesN<32>>,
+            _proof: Groth16Proof,
+        ) -> bool {
+            true
+        }
+    }
+
+    /// Stands in for `ultrahonk-verifier`, accepting every proof. Its
+    /// built-in VK hashes to `digest(5)` and registered ones to `digest(6)`.
+    #[contract]
+    struct AcceptingUltraHonkVerifier;
+
+    #[contractimpl]
+    impl AcceptingUltraHonkVerifier {
+        pub fn verify_proof(_env: Env, _public_inputs: Bytes, _proof_bytes: Bytes) -> bool {
+            true
+        }
+
+        pub fn verify_proof_vk(_env: Env, _vk_id: Symbol, _public_inputs: Bytes, _proof_bytes: Bytes) -> bool {
+            true
+        }
+
+        pub fn vk_hash(env: Env) -> Option<BytesN<32>> {
+            Some(BytesN::from_array(&env, &[5; 32]))
+        }
+
+        pub fn vk_hash_by_id(env: Env, _vk_id: Symbol) -> Option<BytesN<32>> {
+            Some(BytesN::from_array(&env, &[6; 32]))
+        }
+    }
+
+    /// Public inputs as 32-byte big-endian fields.
+    fn fields(env: &Env, inputs: &[u64]) -> Vec<BytesN<32>> {
+        let mut out = Vec::new(env);
+        for input in inputs {
+            let mut field = [0u8; 32];
+            field[24..].copy_from_slice(&input.to_be_bytes());
+            out.push_back(BytesN::from_array(env, &field));
+        }
+        out
+    }
+
+    /// `x + r`: the same scalar as `x` once reduced, but a different input.
+    fn plus_modulus(x: u8) -> [u8; 32] {
+        let mut field = BN254_SCALAR_MODULUS;
+        field[31] += x;
+        field
+    }
+
+    /// `owner_field(owner)` followed by `inputs`, for bindings with
+    /// `owner_input: 0`.
+    fn owned(env: &Env, client: &FarmAttestationsClient, owner: &Address, inputs: &[u64]) -> Vec<BytesN<32>> {
+        let mut out = Vec::from_array(env, [client.owner_field(owner)]);
+        out.append(&fields(env, inputs));
+        out
+    }
+
+    /// Public inputs packed as the UltraHonk verifier takes them.
+    fn packed(env: &Env, inputs: &Vec<BytesN<32>>) -> Bytes {
+        let mut out = Bytes::new(env);
+        for field in inputs.iter() {
+            out.append(&field.into());
+        }
+        out
+    }
+
+    fn seal(env: &Env) -> Groth16Proof {
+        Groth16Proof {
+            pi_a: BytesN::from_array(env, &[0; 64]),
+            pi_b: BytesN::from_array(env, &[0; 128]),
+            pi_c: BytesN::from_array(env, &[0; 64]),
+        }
+    }
+
+    /// A VK of points at infinity for `n` public inputs: the pairing check
+    /// passes for any proof, so tests can attest through the Groth16 route.
+    fn open_vk(env: &Env, n: u32) -> Groth16VerificationKey {
+        let mut ic = Vec::new(env);
+        for _ in 0..=n {
+            ic.push_back(BytesN::from_array(env, &[0; 64]));
+        }
+        Groth16VerificationKey {
+            alpha_g1: BytesN::from_array(env, &[0; 64]),
+            beta_g2: BytesN::from_array(env, &[0; 128]),
+            gamma_g2: BytesN::from_array(env, &[0; 128]),
+            delta_g2: BytesN::from_array(env, &[0; 128]),
+            ic,
+        }
+    }
+
+    fn vk(env: &Env, seed: u8) -> (Groth16VerificationKey, BytesN<32>) {
+        let vk = Groth16VerificationKey {
+            alpha_g1: BytesN::from_array(env, &[seed; 64]),
+            beta_g2: BytesN::from_array(env, &[seed; 128]),
+            gamma_g2: BytesN::from_array(env, &[seed; 128]),
+            delta_g2: BytesN::from_array(env, &[seed; 128]),
+            ic: Vec::new(env),
+        };
+        let hash = env.crypto().sha256(&vk.clone().to_xdr(env)).into();
+        (vk, hash)
+    }
+
+    #[test]
+    fn test_index_pages_split_at_page_size() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+
+        let all = generate(&env, INDEX_PAGE_SIZE + 2);
+        for owner in all.iter() {
+            attest(&env, &client, &owner, &system, &tier);
+        }
+        // Re-attesting does not index twice
+        attest(&env, &client, &all.get(0).unwrap(), &system, &tier);
+        assert_eq!(client.count_by_system(&system), INDEX_PAGE_SIZE + 2);
+
+        let first = client.list_by_system_page(&system, &0, &(INDEX_PAGE_SIZE + 10));
+        assert_eq!(owners(&env, &first), all.slice(..INDEX_PAGE_SIZE));
+        let across = client.list_by_system_page(&system, &(INDEX_PAGE_SIZE - 1), &2);
+        assert_eq!(owners(&env, &across), all.slice(INDEX_PAGE_SIZE - 1..INDEX_PAGE_SIZE + 1));
+        let last = client.list_by_system_page(&system, &INDEX_PAGE_SIZE, &INDEX_PAGE_SIZE);
+        assert_eq!(owners(&env, &last), all.slice(INDEX_PAGE_SIZE..));
+        assert_eq!(client.list_by_system_page(&system, &(INDEX_PAGE_SIZE + 2), &1).len(), 0);
+        assert_eq!(owners(&env, &client.list_by_system(&system)), all);
+    }
+
+    #[test]
+    fn test_index_pages_follow_legacy_prefix() {
+        let env = Env::default();
+        let (id, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+
+        // A v3 index of three entries, frozen at upgrade
+        let legacy = generate(&env, 3);
+        env.as_contract(&id, || {
+            let mut index = Vec::new(&env);
+            for owner in legacy.iter() {
+                index.push_back(IndexEntry { owner: owner.clone(), tier: tier.clone() });
+                let badges = Vec::from_array(&env, [Badge { system: system.clone(), tier: tier.clone() }]);
+                env.storage().persistent().set(&DataKey::OwnerIndex(owner.clone()), &badges);
+                env.storage().persistent().set(
+                    &DataKey::Entry((owner.clone(), system.clone(), tier.clone())),
+                    &record(&env, &owner, &system, &tier),
+                );
+            }
+            env.storage().persistent().set(&DataKey::SystemIndex(system.clone()), &index);
+        });
+        assert_eq!(client.count_by_system(&system), 3);
+
+        let fresh = generate(&env, INDEX_PAGE_SIZE);
+        for owner in fresh.iter() {
+            attest(&env, &client, &owner, &system, &tier);
+        }
+        // Legacy entries are already indexed
+        attest(&env, &client, &legacy.get(1).unwrap(), &system, &tier);
+        assert_eq!(client.count_by_system(&system), INDEX_PAGE_SIZE + 3);
+        assert_eq!(client.count_by_owner(&legacy.get(1).unwrap()), 1);
+
+        let mut all = legacy.clone();
+        all.append(&fresh);
+        let across = client.list_by_system_page(&system, &2, &2);
+        assert_eq!(owners(&env, &across), all.slice(2..4));
+        let first = client.list_by_system_page(&system, &0, &INDEX_PAGE_SIZE);
+        assert_eq!(owners(&env, &first), all.slice(..INDEX_PAGE_SIZE));
+        let last = client.list_by_system_page(&system, &INDEX_PAGE_SIZE, &INDEX_PAGE_SIZE);
+        assert_eq!(owners(&env, &last), all.slice(INDEX_PAGE_SIZE..));
+    }
+
+    #[test]
+    fn test_index_ttl_extension_keeps_full_pages_live() {
+        let env = Env::default();
+        env.ledger().with_mut(|l| {
+            l.min_persistent_entry_ttl = 100;
+            l.max_entry_ttl = 1_000;
+        });
+        let (id, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+
+        let all = generate(&env, INDEX_PAGE_SIZE + 1);
+        for owner in all.iter() {
+            attest(&env, &client, &owner, &system, &tier);
+        }
+        let owner = all.get(0).unwrap();
+        let ttl = |key: DataKey| env.as_contract(&id, || env.storage().persistent().get_ttl(&key));
+
+        // Page 0 filled up first and is no longer touched by appends
+        env.ledger().with_mut(|l| l.sequence_number += 600);
+        assert!(ttl(DataKey::SystemPage((system.clone(), 0))) < 500);
+
+        client.extend_system_index_ttl(&system, &0);
+        client.extend_owner_index_ttl(&owner, &0);
+        for key in [
+            DataKey::SystemPage((system.clone(), 0)),
+            DataKey::SystemLen(system.clone()),
+            DataKey::OwnerPage((owner.clone(), 0)),
+            DataKey::OwnerLen(owner.clone()),
+        ] {
+            assert!(ttl(key) > 900);
+        }
+
+        // Past the original TTL, the extended pages are still live
+        env.ledger().with_mut(|l| l.sequence_number += 600);
+        assert!(ttl(DataKey::SystemPage((system.clone(), 0))) > 0);
+        client.extend_system_index_ttl(&system, &1);
+        assert_eq!(owners(&env, &client.list_by_system_page(&system, &0, &INDEX_PAGE_SIZE)), all.slice(..INDEX_PAGE_SIZE));
+        assert_eq!(client.get_passport(&owner).len(), 1);
+        // Pages that were never written are skipped
+        client.extend_system_index_ttl(&system, &7);
+    }
+
+    #[test]
+    fn test_pre_v3_entry_indexed_on_next_attestation() {
+        let env = Env::default();
+        let (id, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+        let owner = Address::generate(&env);
+
+        env.as_contract(&id, || {
+            env.storage().persistent().set(
+                &DataKey::Entry((owner.clone(), system.clone(), tier.clone())),
+                &record(&env, &owner, &system, &tier),
+            );
+        });
+        assert_eq!(client.count_by_owner(&owner), 0);
+
+        attest(&env, &client, &owner, &system, &tier);
+        attest(&env, &client, &owner, &system, &tier);
+        assert_eq!(client.count_by_owner(&owner), 1);
+        assert_eq!(client.count_by_system(&system), 1);
+        assert_eq!(client.get_passport(&owner).get(0).unwrap().tier, tier);
+    }
+
+    #[test]
+    fn test_revocation_blocks_reattestation_until_cleared() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+        let owner = Address::generate(&env);
+        let issuer = Address::generate(&env);
+        client.set_issuer(&system, &issuer);
+
+        let revoked_id = attest(&env, &client, &owner, &system, &tier);
+        client.revoke(&issuer, &owner, &system, &tier, &7);
+        assert!(client.get(&owner, &system, &tier).is_none());
+
+        // Every write path is refused while the revocation stands
+        assert_eq!(
+            client.try_attest(&owner, &system, &tier, &digest(&env, 1), &digest(&env, 2)),
+            Err(Ok(AttestError::Revoked))
+        );
+        assert_eq!(
+            client.try_attest_if_best(&owner, &system, &tier, &digest(&env, 1), &digest(&env, 2), &9),
+            Err(Ok(AttestError::Revoked))
+        );
+        let res = client.try_batch_attest(
+            &owner,
+            &Vec::from_array(&env, [system.clone()]),
+            &Vec::from_array(&env, [tier.clone()]),
+            &Vec::from_array(&env, [digest(&env, 1)]),
+            &Vec::from_array(&env, [digest(&env, 2)]),
+            &Vec::from_array(&env, [0u64]),
+        );
+        assert_eq!(res, Err(Ok(AttestError::Revoked)));
+
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            client.try_clear_revocation(&stranger, &owner, &system, &tier),
+            Err(Ok(RevocationError::NotAuthorized))
+        );
+        client.clear_revocation(&issuer, &owner, &system, &tier);
+        assert_eq!(
+            client.try_clear_revocation(&issuer, &owner, &system, &tier),
+            Err(Ok(RevocationError::NotFound))
+        );
+        // Clearing does not reinstate the revoked record
+        assert!(client.get(&owner, &system, &tier).is_none());
+        assert_eq!(client.get_revocation(&owner, &system, &tier).unwrap().attestation_id, revoked_id);
+
+        let id = attest(&env, &client, &owner, &system, &tier);
+        assert_eq!(client.get(&owner, &system, &tier).unwrap().attestation_id, id);
+        assert!(client.get_revocation(&owner, &system, &tier).is_none());
+    }
+
+    #[test]
+    fn test_revocation_clears_world_record() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+        let (leader, runner_up) = (Address::generate(&env), Address::generate(&env));
+
+        client.attest_with_score(&runner_up, &system, &tier, &digest(&env, 1), &digest(&env, 2), &5);
+        let id = client.attest_with_score(&leader, &system, &tier, &digest(&env, 1), &digest(&env, 2), &9);
+        assert_eq!(client.get_record(&system, &digest(&env, 2)).unwrap().attestation_id, id);
+
+        // Revoking the runner-up leaves the record alone
+        client.revoke(&client.admin(), &runner_up, &system, &tier, &1);
+        assert_eq!(client.get_record(&system, &digest(&env, 2)).unwrap().owner, leader);
+
+        client.revoke(&client.admin(), &leader, &system, &tier, &1);
+        assert!(client.get_record(&system, &digest(&env, 2)).is_none());
+
+        // The next score takes the record from scratch
+        let other = Address::generate(&env);
+        client.attest_with_score(&other, &system, &tier, &digest(&env, 1), &digest(&env, 2), &3);
+        assert_eq!(client.get_record(&system, &digest(&env, 2)).unwrap().owner, other);
+    }
+
+    #[test]
+    fn test_records_expire_after_validity_window() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let system = symbol_short!("farm");
+        let tier = symbol_short!("gold");
+        let owner = Address::generate(&env);
+        let pair = (Vec::from_array(&env, [system.clone()]), Vec::from_array(&env, [tier.clone()]));
+        client.set_validity(&system, &10);
+
+        attest(&env, &client, &owner, &system, &tier);
+        env.ledger().with_mut(|l| l.sequence_number += 10);
+        assert!(client.get(&owner, &system, &tier).is_some());
+        assert!(client.has(&owner, &system, &tier, &digest(&env, 1)));
+        assert!(client.check_access(&owner, &pair.0, &pair.1));
+
+        env.ledger().with_mut(|l| l.sequence_number += 1);
+        assert!(client.get(&owner, &system, &tier).is_none());
+        assert!(!client.has(&owner, &system, &tier, &digest(&env, 1)));
+        assert!(!client.check_access(&owner, &pair.0, &pair.1));
+
+        // Still indexed; a fresh attestation restarts the window
+        assert_eq!(client.count_by_system(&system), 1);
+        attest(&env, &client, &owner, &system, &tier);
+        assert!(client.check_access(&owner, &pair.0, &pair.1));
+    }
+
+    #[test]
+    fn test_digest_only_cannot_forge_bound_system() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let tier = symbol_short!("gold");
+        let owner = Address::generate(&env);
+        let bound = symbol_short!("circom");
+        let risc0 = symbol_short!("risc0");
+        client.register_binding(
+            &ProofRoute::Groth16,
+            &symbol_short!("tier"),
+            &AttestationBinding {
+                system: bound.clone(),
+                tier_input: 1,
+                tiers: Vec::from_array(&env, [tier.clone()]),
+                score_input: None,
+                owner_input: 0,
+                vk_hash: None,
+            },
+        );
+        client.allow_risc0_image(
+            &digest(&env, 7),
+            &Risc0Image {
+                system: risc0.clone(),
+                tiers: Vec::from_array(&env, [tier.clone()]),
+                min_thresholds: Vec::from_array(&env, [0]),
+            },
+        );
+
+        for system in [bound, risc0] {
+            assert!(client.is_bound_system(&system));
+            assert_eq!(
+                client.try_attest(&owner, &system, &tier, &digest(&env, 1), &digest(&env, 2)),
+                Err(Ok(AttestError::SystemBound))
+            );
+            assert_eq!(
+                client.try_attest_with_score(&owner, &system, &tier, &digest(&env, 1), &digest(&env, 2), &9),
+                Err(Ok(AttestError::SystemBound))
+            );
+            assert_eq!(
+                client.try_attest_if_best(&owner, &system, &tier, &digest(&env, 1), &digest(&env, 2), &9),
+                Err(Ok(AttestError::SystemBound))
+            );
+            // One bound system fails the whole batch
+            let res = client.try_batch_attest(
+                &owner,
+                &Vec::from_array(&env, [symbol_short!("farm"), system.clone()]),
+                &Vec::from_array(&env, [tier.clone(), tier.clone()]),
+                &Vec::from_array(&env, [digest(&env, 1), digest(&env, 1)]),
+                &Vec::from_array(&env, [digest(&env, 2), digest(&env, 2)]),
+                &Vec::from_array(&env, [0u64, 0]),
+            );
+            assert_eq!(res, Err(Ok(AttestError::SystemBound)));
+            assert!(client.get(&owner, &system, &tier).is_none());
+        }
+
+        // Unbound systems stay open
+        assert!(!client.is_bound_system(&symbol_short!("farm")));
+        attest(&env, &client, &owner, &symbol_short!("farm"), &tier);
+        assert_eq!(client.count_by_owner(&owner), 1);
+    }
+
+    #[test]
+    fn test_ultrahonk_binding_derives_tier_and_score() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        client.set_ultrahonk_verifier(&env.register(AcceptingUltraHonkVerifier, ()));
+        let system = symbol_short!("noir");
+        let id = symbol_short!("tier");
+        let tiers = Vec::from_array(&env, [symbol_short!("bronze"), symbol_short!("silver"), symbol_short!("gold")]);
+        let binding = AttestationBinding {
+            system: system.clone(),
+            tier_input: 2,
+            tiers: tiers.clone(),
+            score_input: Some(1),
+            owner_input: 0,
+            vk_hash: Some(digest(&env, 5)),
+        };
+        client.register_binding(&ProofRoute::UltraHonkDefault, &id, &binding);
+        let owner = Address::generate(&env);
+        let proof = Bytes::new(&env);
+        let inputs = |values: &[u64]| packed(&env, &owned(&env, &client, &owner, values));
+
+        // Owner from input 0, score from input 1, tier index from input 2
+        client.verify_ultrahonk_and_attest(&owner, &id, &inputs(&[700, 2]), &proof);
+        let rec = client.get(&owner, &system, &symbol_short!("gold")).unwrap();
+        assert_eq!(rec.score, 700);
+        assert_eq!(rec.statement_hash, client.statement_hash(&id, &owned(&env, &client, &owner, &[700, 2])));
+
+        // Unknown binding, out-of-range tier index, oversized, missing,
+        // non-canonical and ragged inputs
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &symbol_short!("nope"), &inputs(&[700, 0]), &proof),
+            Err(Ok(UltraHonkError::UnknownBinding))
+        );
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &inputs(&[700, 3]), &proof),
+            Err(Ok(UltraHonkError::InvalidPublicInputs))
+        );
+        let mut wide = inputs(&[700, 1]);
+        wide.set(64, 1);
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &wide, &proof),
+            Err(Ok(UltraHonkError::InvalidPublicInputs))
+        );
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &inputs(&[700]), &proof),
+            Err(Ok(UltraHonkError::InvalidPublicInputs))
+        );
+        let mut aliased = inputs(&[200, 1]);
+        aliased.copy_from_slice(32, &plus_modulus(200));
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &aliased, &proof),
+            Err(Ok(UltraHonkError::InvalidPublicInputs))
+        );
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &inputs(&[700, 1]).slice(..95), &proof),
+            Err(Ok(UltraHonkError::InvalidPublicInputs))
+        );
+        // Inputs naming no owner
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &packed(&env, &fields(&env, &[0, 700, 1])), &proof),
+            Err(Ok(UltraHonkError::OwnerMismatch))
+        );
+
+        // Without a score input the score is 0
+        let vk_id = symbol_short!("vk");
+        client.register_binding(
+            &ProofRoute::UltraHonk,
+            &vk_id,
+            &AttestationBinding {
+                system: system.clone(),
+                tier_input: 1,
+                tiers,
+                score_input: None,
+                owner_input: 0,
+                vk_hash: None,
+            },
+        );
+        client.verify_ultrahonk_vk_and_attest(&owner, &vk_id, &inputs(&[0]), &proof);
+        assert_eq!(client.get(&owner, &system, &symbol_short!("bronze")).unwrap().score, 0);
+        // A binding of one route does not apply to another
+        assert_eq!(
+            client.try_verify_ultrahonk_vk_and_attest(&owner, &id, &inputs(&[700, 1]), &proof),
+            Err(Ok(UltraHonkError::UnknownBinding))
+        );
+    }
+
+    #[test]
+    fn test_ultrahonk_proof_attests_once_for_its_owner_under_pinned_vk() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        client.set_ultrahonk_verifier(&env.register(AcceptingUltraHonkVerifier, ()));
+        let system = symbol_short!("noir");
+        let id = symbol_short!("tier");
+        let mut binding = AttestationBinding {
+            system: system.clone(),
+            tier_input: 1,
+            tiers: Vec::from_array(&env, [symbol_short!("gold")]),
+            score_input: None,
+            owner_input: 0,
+            vk_hash: None,
+        };
+        // The built-in VK can be swapped, so its binding must pin one
+        assert!(client.try_register_binding(&ProofRoute::UltraHonkDefault, &id, &binding).is_err());
+        binding.vk_hash = Some(digest(&env, 9));
+        client.register_binding(&ProofRoute::UltraHonkDefault, &id, &binding);
+        client.register_binding(&ProofRoute::UltraHonk, &id, &binding);
+
+        let (owner, thief) = (Address::generate(&env), Address::generate(&env));
+        let inputs = packed(&env, &owned(&env, &client, &owner, &[0]));
+        let proof = Bytes::new(&env);
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &inputs, &proof),
+            Err(Ok(UltraHonkError::VkMismatch))
+        );
+        assert_eq!(
+            client.try_verify_ultrahonk_vk_and_attest(&owner, &id, &inputs, &proof),
+            Err(Ok(UltraHonkError::VkMismatch))
+        );
+
+        binding.vk_hash = Some(digest(&env, 5));
+        client.register_binding(&ProofRoute::UltraHonkDefault, &id, &binding);
+        client.verify_ultrahonk_and_attest(&owner, &id, &inputs, &proof);
+
+        // A seen proof names its owner, so another address cannot submit it
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&thief, &id, &inputs, &proof),
+            Err(Ok(UltraHonkError::OwnerMismatch))
+        );
+        binding.vk_hash = Some(digest(&env, 6));
+        client.register_binding(&ProofRoute::UltraHonk, &id, &binding);
+        assert_eq!(
+            client.try_verify_ultrahonk_vk_and_attest(&thief, &id, &inputs, &proof),
+            Err(Ok(UltraHonkError::OwnerMismatch))
+        );
+        assert!(client.get(&thief, &system, &symbol_short!("gold")).is_none());
+        // and its owner cannot replay it
+        assert_eq!(
+            client.try_verify_ultrahonk_and_attest(&owner, &id, &inputs, &proof),
+            Err(Ok(UltraHonkError::StatementUsed))
+        );
+    }
+
+    #[test]
+    fn test_decode_tier_journal() {
+        let env = Env::default();
+        let mut commitment = [0u8; 32];
+        commitment[0] = 0xAB;
+        commitment[31] = 0xFF;
+
+        let journal = tier_journal(&env, 2, 0x1_0000_0005, commitment, [7; 32]);
+        assert_eq!(journal.len(), RISC0_TIER_JOURNAL_LEN);
+        let (tier_index, threshold, decoded, owner_field) =
+            FarmAttestations::decode_tier_journal(&env, &journal).unwrap();
+        assert_eq!((tier_index, threshold), (2, 0x1_0000_0005));
+        assert_eq!(decoded, BytesN::from_array(&env, &commitment));
+        assert_eq!(owner_field, digest(&env, 7));
+
+        // Wrong length, including the pre-owner journal
+        let mut long = journal.clone();
+        long.push_back(0);
+        assert!(FarmAttestations::decode_tier_journal(&env, &long).is_none());
+        assert!(FarmAttestations::decode_tier_journal(&env, &journal.slice(..140)).is_none());
+        // tier_index beyond u8
+        assert!(FarmAttestations::decode_tier_journal(&env, &tier_journal(&env, 256, 0, commitment, [7; 32])).is_none());
+        // A commitment or owner byte word beyond u8
+        for offset in [12 + 1, 140 + 1] {
+            let mut bad = journal.clone();
+            bad.set(offset, 1);
+            assert!(FarmAttestations::decode_tier_journal(&env, &bad).is_none());
+        }
+    }
+
+    #[test]
+    fn test_risc0_tier_needs_min_threshold_and_journal_owner() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let system = symbol_short!("risc0");
+        let image_id = digest(&env, 7);
+        client.set_risc0_verifier(&Risc0Verifier {
+            verifier: env.register(AcceptingReceiptVerifier, ()),
+            control_root_0: digest(&env, 3),
+            control_root_1: digest(&env, 4),
+            bn254_control_id: digest(&env, 5),
+        });
+        let mut image = Risc0Image {
+            system: system.clone(),
+            tiers: Vec::from_array(&env, [symbol_short!("sprout"), symbol_short!("whale")]),
+            min_thresholds: Vec::from_array(&env, [0]),
+        };
+        assert_eq!(
+            client.try_allow_risc0_image(&image_id, &image),
+            Err(Ok(Risc0Error::ThresholdsMismatch))
+        );
+        assert!(client.get_risc0_image(&image_id).is_none());
+        image.min_thresholds.push_back(1_000);
+        client.allow_risc0_image(&image_id, &image);
+        let (owner, thief) = (Address::generate(&env), Address::generate(&env));
+        let owner_field = client.owner_field(&owner).to_array();
+        let journal = tier_journal(&env, 1, 1_000, [9; 32], owner_field);
+
+        // The journal names its owner, so another address cannot submit it
+        assert_eq!(
+            client.try_verify_risc0_and_attest(&thief, &image_id, &journal, &seal(&env)),
+            Err(Ok(Risc0Error::OwnerMismatch))
+        );
+        assert!(client.get_risc0_commitment(&digest(&env, 9)).is_none());
+
+        // The top tier with a threshold under its minimum
+        let cheap = tier_journal(&env, 1, 999, [9; 32], owner_field);
+        assert_eq!(
+            client.try_verify_risc0_and_attest(&owner, &image_id, &cheap, &seal(&env)),
+            Err(Ok(Risc0Error::ThresholdTooLow))
+        );
+        assert!(client.get(&owner, &system, &symbol_short!("whale")).is_none());
+
+        client.verify_risc0_and_attest(&owner, &image_id, &journal, &seal(&env));
+        let rec = client.get(&owner, &system, &symbol_short!("whale")).unwrap();
+        assert_eq!((rec.score, rec.statement_hash), (1_000, digest(&env, 9)));
+        assert_eq!(client.get_risc0_commitment(&digest(&env, 9)), Some(owner.clone()));
+
+        // The public receipt attests once, and never for another owner
+        assert_eq!(
+            client.try_verify_risc0_and_attest(&owner, &image_id, &journal, &seal(&env)),
+            Err(Ok(Risc0Error::CommitmentUsed))
+        );
+        assert_eq!(
+            client.try_verify_risc0_and_attest(&thief, &image_id, &journal, &seal(&env)),
+            Err(Ok(Risc0Error::OwnerMismatch))
+        );
+        assert!(client.get(&thief, &system, &symbol_short!("whale")).is_none());
+    }
+
+    #[test]
+    fn test_vk_status_only_moves_forward() {
+        let env = Env::default();
+        let (_, client) = setup(&env);
+        let vk_id = symbol_short!("tier");
+        let (vk1, hash1) = vk(&env, 1);
+        let (vk2, hash2) = vk(&env, 2);
+
+        assert_eq!(client.try_register_groth16_vk(&vk_id, &vk1, &hash2), Err(Ok(Groth16Error::VkHashMismatch)));
+        assert_eq!(client.list_groth16_vk_versions(&vk_id).len(), 0);
+        assert_eq!(client.register_groth16_vk(&vk_id, &vk1, &hash1), 1);
+        assert_eq!(client.register_groth16_vk(&vk_id, &vk2, &hash2), 2);
+        assert_eq!(client.list_groth16_vks(), Vec::from_array(&env, [vk_id.clone()]));
+
+        // Active -> Deprecated -> Removed
+        client.deprecate_groth16_vk(&vk_id, &2);
+        assert_eq!(client.try_deprecate_groth16_vk(&vk_id, &2), Err(Ok(Groth16Error::InvalidVkStatus)));
+        client.remove_groth16_vk(&vk_id, &2);
+        assert_eq!(client.try_deprecate_groth16_vk(&vk_id, &2), Err(Ok(Groth16Error::InvalidVkStatus)));
+        assert_eq!(client.try_remove_groth16_vk(&vk_id, &2), Err(Ok(Groth16Error::InvalidVkStatus)));
+        assert!(client.get_groth16_vk(&vk_id, &2).is_none());
+        // Version 1 is the newest active one again
+        assert!(client.has_groth16_vk(&vk_id));
+
+        // Active -> Removed; unknown versions are rejected
+        client.remove_groth16_vk(&vk_id, &1);
+        assert!(!client.has_groth16_vk(&vk_id));
+        assert_eq!(
+            client.try_verify_groth16(&vk_id, &Vec::new(&env), &seal(&env)),
+            Err(Ok(Groth16Error::InactiveVk))
+        );
+        assert_eq!(client.try_deprecate_groth16_vk(&vk_id, &3), Err(Ok(Groth16Error::UnknownVk)));
+        assert_eq!(client.try_deprecate_groth16_vk(&symbol_short!("none"), &1), Err(Ok(Groth16Error::UnknownVk)));
+
+        let versions = client.list_groth16_vk_versions(&vk_id);
+        assert_eq!(versions.get(0).unwrap().status, VkStatus::Removed);
+        assert_eq!(versions.get(1).unwrap().hash, hash2);
+
+        // A new version becomes current again
+        assert_eq!(client.register_groth16_vk(&vk_id, &vk1, &hash1), 3);
+        assert!(client.has_groth16_vk(&vk_id));
+    }
+
+    #[test]
+    fn test_legacy_vk_migrates_and_attestations_name_their_version() {
+        let env = Env::default();
+        let (id, client) = setup(&env);
+        let vk_id = symbol_short!("tier");
+        let system = symbol_short!("circom");
+        let gold = symbol_short!("gold");
+        client.register_binding(
+            &ProofRoute::Groth16,
+            &vk_id,
+            &AttestationBinding {
+                system: system.clone(),
+                tier_input: 1,
+                tiers: Vec::from_array(&env, [gold.clone()]),
+                score_input: None,
+                owner_input: 0,
+                vk_hash: None,
+            },
+        );
+
+        // A key stored before versioning reads as an unlisted version 1
+        let legacy = open_vk(&env, 3);
+        env.as_contract(&id, || {
+            env.storage().persistent().set(&DataKey::Groth16Vk(vk_id.clone()), &legacy);
+        });
+        assert_eq!(client.list_groth16_vks().len(), 0);
+        let versions = client.list_groth16_vk_versions(&vk_id);
+        assert_eq!((versions.len(), versions.get(0).unwrap().registered_ledger), (1, 0));
+        assert!(client.has_groth16_vk(&vk_id));
+
+        // Three public inputs: the owner, the tier index and a per-proof nonce
+        let prove = |owner: &Address, nonce: u64| {
+            client.verify_groth16_and_attest(owner, &vk_id, &owned(&env, &client, owner, &[0, nonce]), &seal(&env))
+        };
+        let prover = Address::generate(&env);
+        let first = prove(&prover, 1);
+        // Another address cannot submit the prover's proof
+        assert_eq!(
+            client.try_verify_groth16_and_attest(
+                &Address::generate(&env),
+                &vk_id,
+                &owned(&env, &client, &prover, &[0, 1]),
+                &seal(&env)
+            ),
+            Err(Ok(Groth16Error::OwnerMismatch))
+        );
+        // The same statement with its nonce aliased mod r is not a new one
+        let mut aliased = owned(&env, &client, &prover, &[0]);
+        aliased.push_back(BytesN::from_array(&env, &plus_modulus(1)));
+        assert_eq!(
+            client.try_verify_groth16_and_attest(&prover, &vk_id, &aliased, &seal(&env)),
+            Err(Ok(Groth16Error::InvalidPublicInputs))
+        );
+        assert_eq!(client.get_attestation_vk(&first).unwrap().version, 1);
+
+        client.migrate_groth16_vk(&vk_id);
+        assert_eq!(client.list_groth16_vks(), Vec::from_array(&env, [vk_id.clone()]));
+        assert_eq!(client.list_groth16_vk_versions(&vk_id).get(0).unwrap().hash, versions.get(0).unwrap().hash);
+        env.as_contract(&id, || {
+            assert!(!env.storage().persistent().has(&DataKey::Groth16Vk(vk_id.clone())));
+        });
+        // Migrating again is a no-op
+        client.migrate_groth16_vk(&vk_id);
+        assert_eq!(client.list_groth16_vk_versions(&vk_id).len(), 1);
+        assert_eq!(client.get_groth16_vk(&vk_id, &1).unwrap().ic.len(), 4);
+
+        // New attestations use the newest active version
+        let next = open_vk(&env, 4);
+        let next_hash = env.crypto().sha256(&next.clone().to_xdr(&env)).into();
+        assert_eq!(client.register_groth16_vk(&vk_id, &next, &next_hash), 2);
+        let owner = Address::generate(&env);
+        assert_eq!(
+            client.try_verify_groth16_and_attest(&owner, &vk_id, &owned(&env, &client, &owner, &[0, 2]), &seal(&env)),
+            Err(Ok(Groth16Error::InvalidPublicInputs))
+        );
+        let second =
+            client.verify_groth16_and_attest(&owner, &vk_id, &owned(&env, &client, &owner, &[0, 2, 0]), &seal(&env));
+        let vk_ref = client.get_attestation_vk(&second).unwrap();
+        assert_eq!((vk_ref.vk_id, vk_ref.version), (vk_id.clone(), 2));
+        assert_eq!(client.get(&owner, &system, &gold).unwrap().verifier_hash, next_hash);
+
+        client.deprecate_groth16_vk(&vk_id, &2);
+        let third = prove(&Address::generate(&env), 3);
+        assert_eq!(client.get_attestation_vk(&third).unwrap().version, 1);
+        // Digest-only attestations have no VK
+        assert!(client.get_attestation_vk(&attest(&env, &client, &owner, &symbol_short!("farm"), &gold)).is_none());
+    }
+}
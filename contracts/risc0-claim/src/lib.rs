@@ -0,0 +1,96 @@
+//! RISC Zero receipt claims.
+//!
+//! A risc0-groth16-verifier receipt proves a claim digest, exposed to the
+//! Groth16 verifier as two field elements. Contracts that accept receipts
+//! recompute that digest from the guest image ID and the journal they
+//! expect, so a receipt can only attest to that exact journal.
+
+#![no_std]
+
+use soroban_sdk::{Bytes, BytesN, Env};
+
+/// Digest of a successful RISC Zero `ReceiptClaim` for `image_id` with
+/// `journal`, no input and no assumptions.
+pub fn claim_digest(env: &Env, image_id: &BytesN<32>, journal: &Bytes) -> BytesN<32> {
+    let zero = BytesN::from_array(env, &[0u8; 32]);
+    let journal_digest: BytesN<32> = env.crypto().sha256(journal).into();
+    // Halted, pc = 0, empty memory root
+    let post_state = tagged_struct(env, "risc0.SystemState", core::slice::from_ref(&zero), &[0]);
+    let output = tagged_struct(env, "risc0.Output", &[journal_digest, zero.clone()], &[]);
+    tagged_struct(
+        env,
+        "risc0.ReceiptClaim",
+        &[zero, image_id.clone(), post_state, output],
+        // exit code Halted(0): (sys_exit, user_exit)
+        &[0, 0],
+    )
+}
+
+/// Split a digest into the two field elements a RISC Zero Groth16 receipt
+/// exposes: the byte-reversed digest, low half first.
+pub fn split_digest(env: &Env, digest: &BytesN<32>) -> (BytesN<32>, BytesN<32>) {
+    let mut reversed = digest.to_array();
+    reversed.reverse();
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+    first[16..].copy_from_slice(&reversed[16..]);
+    second[16..].copy_from_slice(&reversed[..16]);
+    (BytesN::from_array(env, &first), BytesN::from_array(env, &second))
+}
+
+/// sha256(sha256(tag) || down.. || data as u32 LE.. || down.len() as u16 LE)
+fn tagged_struct(env: &Env, tag: &str, down: &[BytesN<32>], data: &[u32]) -> BytesN<32> {
+    let tag_digest: BytesN<32> = env.crypto().sha256(&Bytes::from_slice(env, tag.as_bytes())).into();
+    let mut buf = Bytes::new(env);
+    buf.append(&tag_digest.into());
+    for digest in down {
+        buf.append(&digest.clone().into());
+    }
+    for word in data {
+        buf.extend_from_array(&word.to_le_bytes());
+    }
+    buf.extend_from_array(&(down.len() as u16).to_le_bytes());
+    env.crypto().sha256(&buf).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_system_state_digest_matches_risc0() {
+        // risc0_zkvm::SystemState { pc: 0, merkle_root: Digest::ZERO }.digest()
+        let env = Env::default();
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        let digest = tagged_struct(&env, "risc0.SystemState", &[zero], &[0]);
+        assert_eq!(
+            digest,
+            BytesN::from_array(
+                &env,
+                &[
+                    0xa3, 0xac, 0xc2, 0x71, 0x17, 0x41, 0x89, 0x96,
+                    0x34, 0x0b, 0x84, 0xe5, 0xa9, 0x0f, 0x3e, 0xf4,
+                    0xc4, 0x9d, 0x22, 0xc7, 0x9e, 0x44, 0xaa, 0xd8,
+                    0x22, 0xec, 0x9c, 0x31, 0x3e, 0x1e, 0xb8, 0xe2,
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_split_digest_reverses_halves() {
+        let env = Env::default();
+        let mut arr = [0u8; 32];
+        for (i, b) in arr.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let (first, second) = split_digest(&env, &BytesN::from_array(&env, &arr));
+        let first = first.to_array();
+        let second = second.to_array();
+        assert_eq!(first[..16], [0u8; 16]);
+        assert_eq!(first[16], 15);
+        assert_eq!(first[31], 0);
+        assert_eq!(second[16], 31);
+        assert_eq!(second[31], 16);
+    }
+}
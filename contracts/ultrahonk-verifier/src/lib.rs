@@ -102,6 +102,19 @@ impl UltraHonkVerifierUpgradeable {
         env.storage().instance().has(&DataKey::VkById(vk_id))
     }
 
+    /// sha256 of the VK `verify_proof` uses, if one is set. Lets callers pin
+    /// the key a proof was verified under.
+    pub fn vk_hash(env: Env) -> Option<BytesN<32>> {
+        let vk_bytes = Self::default_vk(&env)?;
+        Some(env.crypto().sha256(&vk_bytes).into())
+    }
+
+    /// sha256 of the VK registered under `vk_id`, if any.
+    pub fn vk_hash_by_id(env: Env, vk_id: soroban_sdk::Symbol) -> Option<BytesN<32>> {
+        let vk_bytes: Bytes = env.storage().instance().get(&DataKey::VkById(vk_id))?;
+        Some(env.crypto().sha256(&vk_bytes).into())
+    }
+
     /// Verify an UltraHonk proof using the stored VK.
     ///
     /// NOTE: `public_inputs` must be encoded exactly as expected by the verifier library.
@@ -110,13 +123,7 @@ impl UltraHonkVerifierUpgradeable {
             return false;
         }
 
-        // Prefer registry default if present; fall back to legacy single VK key.
-        let vk_bytes: Option<Bytes> = match env.storage().instance().get::<_, soroban_sdk::Symbol>(&DataKey::DefaultVkId) {
-            Some(vk_id) => env.storage().instance().get(&DataKey::VkById(vk_id)),
-            None => env.storage().instance().get(&DataKey::Vk),
-        };
-
-        let Some(vk_bytes) = vk_bytes else { return false; };
+        let Some(vk_bytes) = Self::default_vk(&env) else { return false; };
 
         let verifier = UltraHonkVerifier::new(&env, &vk_bytes);
         let Ok(verifier) = verifier else {
@@ -148,6 +155,14 @@ impl UltraHonkVerifierUpgradeable {
         Self::bump_instance_ttl(&env);
     }
 
+    // Prefer registry default if present; fall back to legacy single VK key.
+    fn default_vk(env: &Env) -> Option<Bytes> {
+        match env.storage().instance().get::<_, soroban_sdk::Symbol>(&DataKey::DefaultVkId) {
+            Some(vk_id) => env.storage().instance().get(&DataKey::VkById(vk_id)),
+            None => env.storage().instance().get(&DataKey::Vk),
+        }
+    }
+
     fn bump_instance_ttl(env: &Env) {
         let max = env.storage().max_ttl();
         let threshold = if max > 1 { max / 2 } else { 1 };
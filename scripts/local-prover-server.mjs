@@ -86,11 +86,13 @@ async function handleRisc0Groth16(body) {
   const threshold = Number(body?.threshold);
   const balance = Number(body?.balance);
   const saltByte = Number(body?.saltByte);
+  const ownerField = String(body?.ownerField ?? "");
 
   if (!Number.isFinite(tierIndex) || tierIndex < 0 || tierIndex > 255) throw new Error("Invalid tierIndex");
   if (!Number.isFinite(threshold) || threshold < 0 || threshold > Number.MAX_SAFE_INTEGER) throw new Error("Invalid threshold");
   if (!Number.isFinite(balance) || balance < 0 || balance > Number.MAX_SAFE_INTEGER) throw new Error("Invalid balance");
   if (!Number.isFinite(saltByte) || saltByte < 0 || saltByte > 255) throw new Error("Invalid saltByte");
+  if (!/^[0-9a-f]{64}$/.test(ownerField)) throw new Error("Invalid ownerField");
 
   const rootFs = IS_WIN ? windowsPathToWslPath(rootDir) : rootDir;
   const risc0Dir = IS_WIN ? `${rootFs}/zk/risc0-tier/host` : path.join(rootFs, "zk", "risc0-tier", "host");
@@ -108,7 +110,7 @@ async function handleRisc0Groth16(body) {
     `cd ${bashSingleQuote(risc0Dir)}`,
     // Force a stable target dir under this package (avoids workspace-level target paths).
     `if [ ! -x target/release/prove_groth16 ]; then RISC0_DEV_MODE=0 cargo build --quiet --release --bin prove_groth16 --target-dir target; fi`,
-    `RISC0_WORK_DIR=${bashSingleQuote(workDir)} RISC0_DEV_MODE=0 ./target/release/prove_groth16 ${tierIndex} ${threshold} ${balance} ${saltByte}`,
+    `RISC0_WORK_DIR=${bashSingleQuote(workDir)} RISC0_DEV_MODE=0 ./target/release/prove_groth16 ${tierIndex} ${threshold} ${balance} ${saltByte} ${ownerField}`,
   ].join(" && ");
 
   const { stdout } = await execFileAsync(IS_WIN ? "wsl" : "bash", IS_WIN ? ["bash", "-lc", cmd] : ["-lc", cmd], {
@@ -1,4 +1,5 @@
 import { execSync } from "child_process";
+import { createHash } from "crypto";
 import fs from "fs";
 import path from "path";
 import { fileURLToPath } from "url";
@@ -179,7 +180,10 @@ async function main() {
   const account = await server.getAccount(kp.publicKey());
 
   const contract = new Contract(CONTRACT_ID);
-  const op = contract.call("register_groth16_vk", scvSymbol(VK_ID), vkVal);
+  // The contract pins the upload to sha256 of the VK's ScVal XDR.
+  const vkHash = createHash("sha256").update(vkVal.toXDR()).digest();
+  console.log(`VK hash: ${vkHash.toString("hex")}`);
+  const op = contract.call("register_groth16_vk", scvSymbol(VK_ID), vkVal, scvBytes(vkHash));
 
   const tx = new TransactionBuilder(account, {
     fee: "10000000", // 1 XLM for mainnet reliability
@@ -3,7 +3,7 @@ import { Buffer } from "buffer";
 import { FARM_ATTESTATIONS_CONTRACT_ID_MAINNET, MAINNET_NETWORK_PASSPHRASE, MAINNET_RPC_URL } from "../../config/farmAttestation";
 import { ensureBytes32Hex, hexToBytes } from "../the-farm/digest";
 import { getRpcUrl } from "../../utils/rpc";
-import { noirUltraHonkLegacySamples, noirUltraHonkLegacyVkDigestHex } from "../../data/dungeon/noirUltraHonkLegacyBundle";
+import { noirUltraHonkLegacySamples } from "../../data/dungeon/noirUltraHonkLegacyBundle";
 import type { DungeonNetworkConfig } from "./networkConfig";
 
 const { Api, Server, assembleTransaction } = rpc;
@@ -83,8 +83,8 @@ export async function publishNoirUltraHonkVerifyMainnet(input: {
   tierId: number;
   proofOverride?: { proofBase64: string; publicInputs: string[] };
   vkId?: string;
-  verifierHashHex?: string;
-  tierTag?: string;
+  // `UltraHonkDefault` binding for the built-in VK route; defaults to `vkId`.
+  bindingId?: string;
   net?: DungeonNetworkConfig;
   onStage?: (stage: "simulating" | "assembling" | "signing" | "submitted" | "confirmed") => void;
 }): Promise<NoirUltraHonkOnchainResult> {
@@ -103,7 +103,6 @@ export async function publishNoirUltraHonkVerifyMainnet(input: {
     const passphrase = net ? net.networkPassphrase : (MAINNET_NETWORK_PASSPHRASE || Networks.PUBLIC);
 
     const tier = tierIdToSampleTier(input.tierId);
-    const tierTag = (input.tierTag ?? tier).toString().toUpperCase().slice(0, 32);
 
     const override = input.proofOverride;
     const sample =
@@ -126,9 +125,8 @@ export async function publishNoirUltraHonkVerifyMainnet(input: {
       throw new Error("Noir sample missing publicInputs");
     }
 
-    const statementHash = ensureBytes32Hex(String((sample as any)?.commitmentDigest ?? publicInputsHex[1] ?? ""));
-    const verifierHash = ensureBytes32Hex(input.verifierHashHex ?? noirUltraHonkLegacyVkDigestHex);
     const vkId = (input.vkId ?? DEFAULT_NOIR_ULTRAHONK_VK_ID).toString().slice(0, 32);
+    const bindingId = (input.bindingId ?? vkId).toString().slice(0, 32);
 
     const proofBytes = Buffer.from(proofBase64, "base64");
     const publicInputsBytes = concatPublicInputsBytes(publicInputsHex);
@@ -145,14 +143,11 @@ export async function publishNoirUltraHonkVerifyMainnet(input: {
       : new Account(NULL_ACCOUNT, "0");
 
     // Prefer the multi-VK entrypoint when available (farm-attestations v2+).
-    // If the on-chain contract hasn't been upgraded yet, we fall back to the legacy method.
+    // If the VK isn't registered, we fall back to the verifier's built-in VK.
+    // The contract reads tier and digests from the verified public inputs.
     const opPreferred = farm.call(
       "verify_ultrahonk_vk_and_attest",
       new Address(input.owner).toScVal(),
-      symbol("NOIR"),
-      symbol(tierTag),
-      xdr.ScVal.scvBytes(Buffer.from(hexToBytes(statementHash))),
-      xdr.ScVal.scvBytes(Buffer.from(hexToBytes(verifierHash))),
       symbol(vkId),
       xdr.ScVal.scvBytes(publicInputsBytes),
       xdr.ScVal.scvBytes(proofBytes),
@@ -161,10 +156,7 @@ export async function publishNoirUltraHonkVerifyMainnet(input: {
     const opLegacy = farm.call(
       "verify_ultrahonk_and_attest",
       new Address(input.owner).toScVal(),
-      symbol("NOIR"),
-      symbol(tierTag),
-      xdr.ScVal.scvBytes(Buffer.from(hexToBytes(statementHash))),
-      xdr.ScVal.scvBytes(Buffer.from(hexToBytes(verifierHash))),
+      symbol(bindingId),
       xdr.ScVal.scvBytes(publicInputsBytes),
       xdr.ScVal.scvBytes(proofBytes),
     );
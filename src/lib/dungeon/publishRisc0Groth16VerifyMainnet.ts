@@ -3,7 +3,7 @@ import { Buffer } from "buffer";
 import { MAINNET_NETWORK_PASSPHRASE, MAINNET_RPC_URL } from "../../config/farmAttestation";
 import { FARM_ATTESTATIONS_CONTRACT_ID_MAINNET } from "../../config/farmAttestation";
 import { RISC0_GROTH16_VERIFIER_CONTRACT_ID_MAINNET } from "../../config/risc0Groth16Verifier";
-import { ensureBytes32Hex, hexToBytes } from "../the-farm/digest";
+import { ensureBytes32Hex, hexToBytes, sha256Hex } from "../the-farm/digest";
 import { getRpcUrl } from "../../utils/rpc";
 import type { DungeonNetworkConfig } from "./networkConfig";
 
@@ -13,10 +13,6 @@ import risc0Groth16Sample from "../../data/dungeon/risc0_groth16_sample.json";
 const { Api, Server, assembleTransaction } = rpc;
 const NULL_ACCOUNT = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
 
-// Universal verifier path (preferred): farm-attestations Groth16 VK registry.
-// Keep <= 32 chars for Symbol.
-const RISC0_GROTH16_VK_ID = "R0G16V1";
-
 export type Risc0Groth16VerifyResult =
   | { ok: true; txHash: string; ledger?: number }
   | { ok: false; error: string; txHash?: string };
@@ -62,6 +58,16 @@ async function waitForTransaction(server: rpc.Server, hash: string): Promise<rpc
   throw new Error("RISC0 Groth16 on-chain verification confirmation timed out");
 }
 
+/**
+ * farm-attestations `owner_field(owner)`: sha256 of the owner's ScVal XDR with
+ * its top byte cleared. The risc0-tier guest commits it to the journal, so the
+ * receipt only attests for `owner`.
+ */
+export async function risc0OwnerFieldHex(owner: string): Promise<string> {
+  const digestHex = await sha256Hex(new Uint8Array(new Address(owner).toScVal().toXDR()));
+  return `00${digestHex.slice(2)}`;
+}
+
 /**
  * On-chain proof verification for a RISC0 Groth16 receipt.
  *
@@ -71,7 +77,9 @@ export async function publishRisc0Groth16VerifyMainnet(input: {
   owner: string;
   keyId: string;
   proofOverride?: {
+    image_id_hex: string;
     claim_digest_hex: string;
+    journal_hex: string;
     public_inputs_hex: string[];
     proof: { pi_a_b64: string; pi_b_b64: string; pi_c_b64: string };
   };
@@ -88,7 +96,12 @@ export async function publishRisc0Groth16VerifyMainnet(input: {
     const passphrase = net ? net.networkPassphrase : (MAINNET_NETWORK_PASSPHRASE || Networks.PUBLIC);
 
     const sample: any = (input.proofOverride ?? (risc0Groth16Sample as any)) as any;
+    const imageIdHex = ensureBytes32Hex(String(sample.image_id_hex ?? ""));
     const claimDigestHex = ensureBytes32Hex(String(sample.claim_digest_hex ?? ""));
+    const journalHex = String(sample.journal_hex ?? "");
+    if (!journalHex) {
+      throw new Error("Invalid RISC0 Groth16 sample: missing journal");
+    }
     const publicInputsHex: string[] = Array.isArray(sample.public_inputs_hex) ? sample.public_inputs_hex : [];
     if (publicInputsHex.length !== 5) {
       throw new Error("Invalid RISC0 Groth16 sample: expected 5 public inputs");
@@ -128,7 +141,8 @@ export async function publishRisc0Groth16VerifyMainnet(input: {
       publicInputsHex.map((hex) => xdr.ScVal.scvBytes(Buffer.from(hexToBytes(ensureBytes32Hex(hex))))),
     );
 
-    // Prefer the "universal" verifier path via farm-attestations (VK registry).
+    // Prefer farm-attestations: it recomputes the claim from image ID and journal
+    // and reads tier, threshold and commitment from the journal.
     const tryUniversalFirst = async (): Promise<{
       tx: any;
       contractLabel: string;
@@ -138,14 +152,10 @@ export async function publishRisc0Groth16VerifyMainnet(input: {
 
       const farm = new Contract(farmId);
       const op = farm.call(
-        "verify_groth16_and_attest",
+        "verify_risc0_and_attest",
         new Address(input.owner).toScVal(),
-        symbol("RISC0"),
-        symbol("SPROUT"),
-        xdr.ScVal.scvBytes(Buffer.from(hexToBytes(claimDigestHex))), // statement_hash
-        xdr.ScVal.scvBytes(Buffer.from(hexToBytes(claimDigestHex))), // verifier_hash (digest-only identifier)
-        symbol(RISC0_GROTH16_VK_ID),
-        publicInputsVec,
+        xdr.ScVal.scvBytes(Buffer.from(hexToBytes(imageIdHex))),
+        xdr.ScVal.scvBytes(Buffer.from(hexToBytes(journalHex))),
         proofStruct,
       );
 
@@ -157,7 +167,7 @@ export async function publishRisc0Groth16VerifyMainnet(input: {
         .setTimeout(TimeoutInfinite)
         .build();
 
-      // Probe simulation: if contract hasn't been upgraded or VK isn't registered, this will fail.
+      // Probe simulation: if contract hasn't been upgraded or the image isn't allowlisted, this will fail.
       const sim = await server.simulateTransaction(tx);
       if (Api.isSimulationError(sim)) {
         return null;
@@ -170,7 +180,7 @@ export async function publishRisc0Groth16VerifyMainnet(input: {
       const contractId = net ? net.risc0Groth16VerifierContractId.trim() : RISC0_GROTH16_VERIFIER_CONTRACT_ID_MAINNET.trim();
       if (!contractId) {
         throw new Error(
-          `RISC0 on-chain verification unavailable: farm-attestations Groth16 registry is not ready and RISC0 verifier contract ID is missing (${net?.network ?? "mainnet"}).`,
+          `RISC0 on-chain verification unavailable: farm-attestations RISC0 route is not ready and RISC0 verifier contract ID is missing (${net?.network ?? "mainnet"}).`,
         );
       }
 
@@ -11,6 +11,8 @@ struct OutputJson {
     system: &'static str,
     image_id_hex: String,
     claim_digest_hex: String,
+    // Guest journal: (tier_index, threshold, commitment, owner_field) in risc0 serde words.
+    journal_hex: String,
     public_inputs_hex: Vec<String>,
     proof: ProofJsonOut,
 }
@@ -93,12 +95,14 @@ fn main() -> Result<()> {
         .init();
 
     // Deterministic sample (matches the tier bundles conceptually).
-    // (tier_index, threshold, balance, salt_bytes32)
+    // (tier_index, threshold, balance, salt_bytes32, owner_field)
+    // The zero owner field matches no address, so the sample cannot attest.
     let tier_index: u8 = 1;
     let threshold: u64 = 100;
     let balance: u64 = 220;
     let salt: [u8; 32] = [22u8; 32];
-    let input = (tier_index, threshold, balance, salt);
+    let owner_field: [u8; 32] = [0u8; 32];
+    let input = (tier_index, threshold, balance, salt, owner_field);
 
     let env = ExecutorEnv::builder()
         .write(&input)
@@ -139,6 +143,7 @@ fn main() -> Result<()> {
         system: "risc0_groth16_receipt",
         image_id_hex: hex::encode(Risc0Digest::from(FARM_TIER_ID).as_bytes()),
         claim_digest_hex: bytes_to_hex(claim_digest.as_bytes()),
+        journal_hex: bytes_to_hex(&receipt.journal.bytes),
         public_inputs_hex,
         proof: ProofJsonOut {
             pi_a_b64: B64.encode(pi_a),
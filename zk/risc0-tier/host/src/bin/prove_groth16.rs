@@ -12,6 +12,8 @@ struct OutputJson {
     system: &'static str,
     image_id_hex: String,
     claim_digest_hex: String,
+    // Guest journal: (tier_index, threshold, commitment, owner_field) in risc0 serde words.
+    journal_hex: String,
     public_inputs_hex: Vec<String>,
     proof: ProofJsonOut,
     // Optional: handy for local verification / debugging in the frontend.
@@ -104,23 +106,37 @@ fn parse_u64(arg: Option<&String>, name: &str, default: u64) -> Result<u64> {
     }
 }
 
+fn parse_bytes32_hex(arg: Option<&String>, name: &str) -> Result<[u8; 32]> {
+    let raw = match arg {
+        Some(raw) => raw.strip_prefix("0x").unwrap_or(raw),
+        None => return Ok([0u8; 32]),
+    };
+    let bytes = hex::decode(raw).with_context(|| format!("invalid {name} (hex): {raw}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid {name}: expected 32 bytes"))
+}
+
 fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
         .init();
 
     // Usage:
-    //   cargo run --bin prove_groth16 -- <tier_index_u8> <threshold_u64> <balance_u64> <salt_byte_u8>
+    //   cargo run --bin prove_groth16 -- <tier_index_u8> <threshold_u64> <balance_u64> <salt_byte_u8> <owner_field_hex>
     //
+    // owner_field_hex is farm-attestations `owner_field(owner)` for the
+    // address that will submit the receipt.
     // Defaults match the sample exporter for reproducibility.
     let args: Vec<String> = env::args().collect();
     let tier_index = parse_u8(args.get(1), "tier_index", 1)?;
     let threshold = parse_u64(args.get(2), "threshold", 100)?;
     let balance = parse_u64(args.get(3), "balance", 220)?;
     let salt_byte = parse_u8(args.get(4), "salt_byte", 22)?;
+    let owner_field = parse_bytes32_hex(args.get(5), "owner_field")?;
 
     let salt: [u8; 32] = [salt_byte; 32];
-    let input = (tier_index, threshold, balance, salt);
+    let input = (tier_index, threshold, balance, salt, owner_field);
 
     let env = ExecutorEnv::builder()
         .write(&input)
@@ -163,6 +179,7 @@ fn main() -> Result<()> {
         system: "risc0_groth16_receipt",
         image_id_hex: hex::encode(Risc0Digest::from(FARM_TIER_ID).as_bytes()),
         claim_digest_hex: hex::encode(claim_digest.as_bytes()),
+        journal_hex: hex::encode(&receipt.journal.bytes),
         public_inputs_hex,
         proof: ProofJsonOut {
             pi_a_b64: B64.encode(pi_a),
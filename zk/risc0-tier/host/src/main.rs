@@ -244,7 +244,8 @@ fn create_bundle() -> Result<Risc0Bundle> {
         let commitment = compute_commitment(case.balance, salt);
         let expected_commitment_hex = hex::encode(commitment);
 
-        let input = (index as u8, case.threshold, case.balance, salt);
+        // Training samples are not bound to any owner
+        let input = (index as u8, case.threshold, case.balance, salt, [0u8; 32]);
 
         let env = ExecutorEnv::builder()
             .write(&input)
@@ -261,7 +262,7 @@ fn create_bundle() -> Result<Risc0Bundle> {
             .verify(FARM_TIER_ID)
             .context("host verification failed for generated receipt")?;
 
-        let journal: (u8, u64, [u8; 32]) = receipt
+        let journal: (u8, u64, [u8; 32], [u8; 32]) = receipt
             .journal
             .decode()
             .context("failed to decode receipt journal")?;
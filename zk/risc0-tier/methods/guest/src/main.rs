@@ -2,7 +2,9 @@ use risc0_zkvm::guest::env;
 use sha2::{Digest, Sha256};
 
 fn main() {
-    let (tier_index, threshold, balance, salt): (u8, u64, u64, [u8; 32]) = env::read();
+    // owner_field: farm-attestations `owner_field(owner)`, committed as is so
+    // the receipt only attests for that owner.
+    let (tier_index, threshold, balance, salt, owner_field): (u8, u64, u64, [u8; 32], [u8; 32]) = env::read();
 
     assert!(balance >= threshold, "balance below threshold");
 
@@ -14,5 +16,5 @@ fn main() {
     let mut commitment_digest = [0u8; 32];
     commitment_digest.copy_from_slice(&digest);
 
-    env::commit(&(tier_index, threshold, commitment_digest));
+    env::commit(&(tier_index, threshold, commitment_digest, owner_field));
 }